@@ -0,0 +1,118 @@
+//! Benchmarks `RpcReader` with a large redraw notification.
+//!
+//! By default, a full screen redraw of a 4K sized grid is generated. To use
+//! a captured session instead, point `NVIM_RS_BENCH_CAPTURE` to a session
+//! log (see the `record` module), e.g. one recorded with `gnvim --record`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::executor::block_on;
+
+use nvim_rs::rpc::{
+    message::Notification,
+    record::{read_entries, Direction},
+    GridLineVisitor, LazyMessage, RedrawEvent, RpcReader, RpcWriter,
+};
+
+const COLS: usize = 480;
+const ROWS: usize = 135;
+
+/// A redraw notification with `frames` full redraws of the grid.
+fn redraw(frames: usize) -> Vec<u8> {
+    let grid_line = (0..frames).flat_map(|frame| {
+        (0..ROWS).map(move |row| {
+            let cells = (0..COLS)
+                .map(|col| {
+                    rmpv::Value::from(vec![
+                        rmpv::Value::from(
+                            ((b'a' + ((row + col + frame) % 26) as u8) as char).to_string(),
+                        ),
+                        rmpv::Value::from(col % 7),
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            rmpv::Value::from(vec![
+                rmpv::Value::from(1),
+                rmpv::Value::from(row),
+                rmpv::Value::from(0),
+                rmpv::Value::from(cells),
+                rmpv::Value::from(false),
+            ])
+        })
+    });
+
+    let event = std::iter::once(rmpv::Value::from("grid_line"))
+        .chain(grid_line)
+        .collect::<Vec<_>>();
+    let params = vec![
+        rmpv::Value::from(event),
+        rmpv::Value::from(vec![rmpv::Value::from("flush")]),
+    ];
+
+    <Vec<u8> as RpcWriter>::encode(&Notification::new("redraw", params)).unwrap()
+}
+
+fn read_all(data: &[u8]) -> usize {
+    let mut reader = RpcReader::new(futures::io::Cursor::new(data));
+    let mut n = 0;
+
+    block_on(async {
+        while reader.recv().await.is_ok() {
+            n += 1;
+        }
+    });
+
+    n
+}
+
+/// Counts the cells of grid_line events.
+struct CountCells(usize);
+
+impl GridLineVisitor for CountCells {
+    fn cell(&mut self, _text: &str, _hl_id: Option<i64>, repeat: Option<i64>) {
+        self.0 += repeat.unwrap_or(1) as usize;
+    }
+}
+
+fn read_all_lazy(data: &[u8]) -> usize {
+    let mut reader = RpcReader::new(futures::io::Cursor::new(data));
+    let mut cells = CountCells(0);
+
+    block_on(async {
+        while let Ok(msg) = reader.recv_lazy().await {
+            if let LazyMessage::Redraw(redraw) = msg {
+                for event in redraw.events() {
+                    if let Ok(RedrawEvent::GridLine(line)) = event {
+                        line.visit_cells(&mut cells).unwrap();
+                    }
+                }
+            }
+        }
+    });
+
+    cells.0
+}
+
+fn bench_reader(c: &mut Criterion) {
+    let data = match std::env::var_os("NVIM_RS_BENCH_CAPTURE") {
+        Some(path) => {
+            let file = std::fs::File::open(path).expect("failed to open the capture");
+            read_entries(std::io::BufReader::new(file))
+                .map(|entry| entry.expect("failed to read the capture"))
+                .filter(|entry| entry.direction == Direction::Incoming)
+                .flat_map(|entry| entry.data)
+                .collect()
+        }
+        None => redraw(10),
+    };
+
+    let mut group = c.benchmark_group("reader");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.sample_size(10);
+    group.bench_function("redraw", |b| b.iter(|| read_all(&data)));
+    group.bench_function("redraw_lazy", |b| b.iter(|| read_all_lazy(&data)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_reader);
+criterion_main!(benches);
@@ -37,6 +37,7 @@ fn uievents(res: ApiMetadata) {
     let members = res.ui_events.iter().map(|event| event.to_enum_arm());
     let display_members = res.ui_events.iter().map(|event| event.to_display_arm());
     let decode_matches = res.ui_events.iter().map(|event| event.to_decode_arm());
+    let error_type = res.error_types.to_tokens();
 
     let out = quote! {
         use std::fmt::Display;
@@ -45,15 +46,23 @@ fn uievents(res: ApiMetadata) {
 
         #(#structs)*
 
+        #error_type
+
         #[derive(Debug)]
         pub enum UiEvent {
             #(#members)*
+            /// Event not known to us (e.g. from newer version of neovim).
+            Unknown {
+                name: String,
+                args: Vec<rmpv::Value>,
+            },
         }
 
         impl Display for UiEvent {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
                     #(#display_members)*
+                    Self::Unknown { name, .. } => write!(f, "{}", name),
                 }
             }
         }
@@ -91,7 +100,10 @@ fn uievents(res: ApiMetadata) {
 
                         Ok(match name.as_str() {
                             #(#decode_matches)*
-                            v => panic!("failed to decode message {:?}", v),
+                            v => UiEvent::Unknown {
+                                name: v.to_owned(),
+                                args: seq_to_vec!(seq),
+                            },
                         })
                     }
                 }
@@ -191,6 +191,44 @@ pub struct ErrorTypes {
     pub validation: ExtErrorType,
 }
 
+impl ErrorTypes {
+    pub fn to_tokens(&self) -> TokenStream {
+        let exception = self.exception.id;
+        let validation = self.validation.id;
+
+        quote! {
+            /// Type of an error response from neovim.
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum ErrorType {
+                Exception,
+                Validation,
+                /// Error type not known to us.
+                Unknown(i64),
+            }
+
+            impl From<i64> for ErrorType {
+                fn from(id: i64) -> Self {
+                    match id {
+                        #exception => Self::Exception,
+                        #validation => Self::Validation,
+                        id => Self::Unknown(id),
+                    }
+                }
+            }
+
+            impl From<ErrorType> for i64 {
+                fn from(kind: ErrorType) -> Self {
+                    match kind {
+                        ErrorType::Exception => #exception,
+                        ErrorType::Validation => #validation,
+                        ErrorType::Unknown(id) => id,
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[derive(Debug, serde::Deserialize)]
 pub struct UiEvent {
     pub parameters: Vec<Parameter>,
@@ -325,6 +363,9 @@ impl UiEvent {
             ("cmdline_block_show", "lines") => quote! { Vec<Vec<CmdlineContent>> },
             ("cmdline_block_append", "lines") => quote! { Vec<CmdlineContent> },
             ("msg_show", "content") => quote! { Vec<MsgShowContent> },
+            ("msg_showcmd", "content") => quote! { Vec<MsgShowContent> },
+            ("msg_showmode", "content") => quote! { Vec<MsgShowContent> },
+            ("msg_ruler", "content") => quote! { Vec<MsgShowContent> },
             ("msg_history_show", "entries") => quote! { Vec<MsgHistoryShowEntry> },
             _ => self.field_type(ty),
         }
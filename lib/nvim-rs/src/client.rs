@@ -1,8 +1,13 @@
 use std::cell::RefCell;
 
-use futures::lock::Mutex;
+use futures::{lock::Mutex, prelude::*};
+use serde::Deserialize;
 
-use crate::rpc::{caller::Sender, message::Response, Caller, HandleError, RpcWriter, WriteError};
+use crate::rpc::{
+    caller::{Callbacks, Sender},
+    message::{Message, Response},
+    CallResponse, Caller, HandleError, ReadError, RpcReader, RpcWriter, WriteError,
+};
 
 #[macro_export]
 macro_rules! dict {
@@ -14,18 +19,20 @@ macro_rules! dict {
     }};
 }
 
+/// A msgpack-rpc client. Calls are made through `Caller`, and cancelled with
+/// `cancel` or by dropping their futures.
 #[derive(Debug)]
 pub struct Client<W: RpcWriter> {
     writer: Mutex<Box<W>>,
     msgid_counter: RefCell<u32>,
-    callbacks: RefCell<Vec<(u32, Sender)>>,
+    callbacks: RefCell<Callbacks>,
 }
 
 impl<W: RpcWriter> Client<W> {
     pub fn new(writer: W) -> Self {
         Self {
             writer: Mutex::new(Box::new(writer)),
-            callbacks: RefCell::new(Vec::new()),
+            callbacks: RefCell::new(Callbacks::default()),
             msgid_counter: RefCell::new(0),
         }
     }
@@ -34,21 +41,71 @@ impl<W: RpcWriter> Client<W> {
         &self,
         response: Response<rmpv::Value, rmpv::Value>,
     ) -> Result<(), HandleError> {
-        let mut callbacks = self.callbacks.borrow_mut();
-        let caller = callbacks
-            .iter()
-            .position(|(msgid, _)| *msgid == response.msgid)
-            .map(|index| callbacks.swap_remove(index));
-
-        match caller {
-            Some((_, recv)) => recv.send(response).map_err(HandleError::CallerDropped),
-            None => Err(HandleError::CallerMissing(response)),
+        self.callbacks.borrow_mut().handle_response(response)
+    }
+
+    /// Makes a call like `Caller::call`, but also returns the call's message id
+    /// for `cancel`.
+    pub fn call_with_id<'a, T, S, V>(
+        &'a self,
+        method: S,
+        args: V,
+    ) -> (u32, impl Future<Output = CallResponse<T>> + 'a)
+    where
+        W: AsyncWrite + Unpin,
+        T: for<'de> Deserialize<'de> + std::any::Any + 'a,
+        S: AsRef<str> + 'a,
+        V: serde::Serialize + 'a,
+    {
+        let mut caller = self;
+        let msgid = caller.next_msgid();
+        let call = caller.call_with_msgid(msgid, method, args, future::pending());
+        (msgid, call)
+    }
+
+    /// Cancels the pending call `msgid`, failing it with `CallError::Cancelled`.
+    /// Returns `false` if the call wasn't pending (i.e. it's done already, or
+    /// its future hasn't been polled yet).
+    pub fn cancel(&self, msgid: u32) -> bool {
+        self.callbacks.borrow_mut().remove(msgid).is_some()
+    }
+
+    /// Number of calls waiting for their response.
+    pub fn pending_calls(&self) -> usize {
+        self.callbacks.borrow().len()
+    }
+
+    /// Fails all pending (and future) calls with `CallError::Disconnected`.
+    /// Called by `recv` once the reader hits EOF.
+    pub fn disconnect(&self) {
+        self.callbacks.borrow_mut().disconnect();
+    }
+
+    /// Reads the next request or notification from `reader`, delivering any
+    /// responses in between to their callers. If the read fails (e.g. on EOF),
+    /// the client is disconnected.
+    pub async fn recv<R: AsyncRead + Unpin>(
+        &self,
+        reader: &mut RpcReader<R>,
+    ) -> Result<Message, ReadError> {
+        loop {
+            match reader.recv().await {
+                Ok(Message::Response(response)) => {
+                    // The caller might've given up on the call already.
+                    let _ = self.handle_response(response);
+                }
+                Ok(msg) => return Ok(msg),
+                Err(err) => {
+                    self.disconnect();
+                    return Err(err);
+                }
+            }
         }
     }
 }
 
 #[async_trait::async_trait(?Send)]
-impl<W: futures::AsyncWrite + Unpin> Caller for &Client<W> {
+impl<W: AsyncWrite + Unpin> Caller for &Client<W> {
     fn next_msgid(&mut self) -> u32 {
         let mut msgid_counter = self.msgid_counter.borrow_mut();
         let msgid = *msgid_counter;
@@ -58,7 +115,11 @@ impl<W: futures::AsyncWrite + Unpin> Caller for &Client<W> {
     }
 
     fn store_handler(&mut self, msgid: u32, sender: Sender) {
-        self.callbacks.borrow_mut().push((msgid, sender));
+        self.callbacks.borrow_mut().insert(msgid, sender);
+    }
+
+    fn remove_handler(&mut self, msgid: u32) -> Option<Sender> {
+        self.callbacks.borrow_mut().remove(msgid)
     }
 
     async fn write<S: AsRef<str>, V: serde::Serialize>(
@@ -1,6 +1,8 @@
 pub mod client;
 mod gen;
 pub mod rpc;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod types;
 
 pub use client::Client;
@@ -1,9 +1,70 @@
 use std::any::{Any, TypeId};
+use std::collections::HashMap;
 
 use futures::{channel::oneshot, prelude::*};
 use serde::Deserialize;
 
 use crate::rpc::{message, WriteError};
+use crate::types::ErrorType;
+
+/// Error response from neovim.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NvimError {
+    pub kind: ErrorType,
+    pub message: String,
+}
+
+impl NvimError {
+    pub fn new<S: Into<String>>(kind: ErrorType, message: S) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Decodes the error from the `[type, message]` array, as described in
+    /// `:h api-metadata`. Returns the original value if it can't be decoded.
+    pub fn decode(value: rmpv::Value) -> Result<Self, rmpv::Value> {
+        match value {
+            rmpv::Value::Array(ref arr) => match arr.as_slice() {
+                [rmpv::Value::Integer(kind), rmpv::Value::String(message)] => {
+                    match (kind.as_i64(), message.as_str()) {
+                        (Some(kind), Some(message)) => Ok(Self {
+                            kind: ErrorType::from(kind),
+                            message: message.to_owned(),
+                        }),
+                        _ => Err(value),
+                    }
+                }
+                _ => Err(value),
+            },
+            value => Err(value),
+        }
+    }
+}
+
+impl std::fmt::Display for NvimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {}", self.kind, self.message)
+    }
+}
+
+impl std::error::Error for NvimError {}
+
+impl serde::Serialize for NvimError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeTuple;
+
+        // Same `[type, message]` array that neovim uses.
+        let mut tup = serializer.serialize_tuple(2)?;
+        tup.serialize_element(&i64::from(self.kind))?;
+        tup.serialize_element(&self.message)?;
+        tup.end()
+    }
+}
 
 #[derive(Debug)]
 pub enum CallError {
@@ -13,20 +74,55 @@ pub enum CallError {
     /// The result field is missing.
     MissingResult,
     /// The call resulted into a error response (e.g. bad API call).
-    Error(rmpv::Value),
+    Error(NvimError),
     /// Decoding the result failed.
-    DecodeResult,
-    // Decoding the error failed.
-    //DecodeError,
+    DecodeResult {
+        error: rmpv::ext::Error,
+        value: rmpv::Value,
+    },
+    /// Decoding the error failed.
+    DecodeError(rmpv::Value),
     /// The write operation over RPC failed.
     WriteError(WriteError),
+    /// No response was received before the timeout.
+    Timeout,
+    /// The connection to neovim was lost before the response was received.
+    Disconnected,
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallError::Cancelled => write!(f, "call cancelled"),
+            CallError::MissingResult => write!(f, "missing result"),
+            CallError::Error(err) => write!(f, "nvim error: {}", err),
+            CallError::DecodeResult { error, value } => {
+                write!(f, "failed to decode result {}: {}", value, error)
+            }
+            CallError::DecodeError(value) => write!(f, "failed to decode error: {}", value),
+            CallError::WriteError(err) => write!(f, "write error: {}", err),
+            CallError::Timeout => write!(f, "call timed out"),
+            CallError::Disconnected => write!(f, "disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for CallError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CallError::Error(err) => Some(err),
+            CallError::DecodeResult { error, .. } => Some(error),
+            CallError::WriteError(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 pub type CallResponse<T> = Result<T, CallError>;
 
 pub type Response = message::Response<rmpv::Value, rmpv::Value>;
 
-pub type Sender = oneshot::Sender<Response>;
+pub type Sender = oneshot::Sender<Result<Response, CallError>>;
 
 impl From<oneshot::Canceled> for CallError {
     fn from(_: oneshot::Canceled) -> Self {
@@ -50,46 +146,171 @@ pub enum HandleError {
     CallerDropped(Response),
 }
 
+/// Pending calls, waiting for their response.
+#[derive(Debug, Default)]
+pub struct Callbacks {
+    senders: HashMap<u32, Sender>,
+    disconnected: bool,
+}
+
+impl Callbacks {
+    pub fn insert(&mut self, msgid: u32, sender: Sender) {
+        if self.disconnected {
+            // Nothing to do if the caller is already gone.
+            let _ = sender.send(Err(CallError::Disconnected));
+        } else {
+            self.senders.insert(msgid, sender);
+        }
+    }
+
+    /// Removes the pending call. Returns `None` if the call wasn't pending.
+    ///
+    /// The call will fail with `CallError::Cancelled`.
+    pub fn remove(&mut self, msgid: u32) -> Option<Sender> {
+        self.senders.remove(&msgid)
+    }
+
+    pub fn len(&self) -> usize {
+        self.senders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    /// Delivers the response to its caller.
+    pub fn handle_response(&mut self, response: Response) -> Result<(), HandleError> {
+        match self.senders.remove(&response.msgid) {
+            Some(sender) => sender.send(Ok(response)).map_err(|res| match res {
+                Ok(response) => HandleError::CallerDropped(response),
+                Err(_) => unreachable!("sent a response"),
+            }),
+            None => Err(HandleError::CallerMissing(response)),
+        }
+    }
+
+    /// Fails all the pending calls, and any calls made after this, with
+    /// `CallError::Disconnected`.
+    pub fn disconnect(&mut self) {
+        self.disconnected = true;
+        for (_, sender) in self.senders.drain() {
+            let _ = sender.send(Err(CallError::Disconnected));
+        }
+    }
+
+    /// Accepts calls again after `disconnect`.
+    pub fn reconnect(&mut self) {
+        self.disconnected = false;
+    }
+}
+
+/// Removes the pending call once dropped, so that calls we've given up on
+/// (i.e. timed out or dropped) won't linger around.
+struct PendingCall<C: Caller> {
+    caller: C,
+    msgid: u32,
+}
+
+impl<C: Caller> Drop for PendingCall<C> {
+    fn drop(&mut self) {
+        self.caller.remove_handler(self.msgid);
+    }
+}
+
+/// Makes calls to neovim.
+///
+/// Dropping the future returned by `call` cancels the call.
 #[async_trait::async_trait(?Send)]
 pub trait Caller
 where
-    Self: Sized,
+    Self: Sized + Clone,
 {
-    async fn call<T, S, V>(mut self, method: S, args: V) -> CallResponse<T>
+    async fn call<T, S, V>(self, method: S, args: V) -> CallResponse<T>
     where
         T: for<'de> Deserialize<'de> + Any,
         S: AsRef<str>,
         V: serde::Serialize,
+    {
+        self.call_with_timeout(method, args, future::pending())
+            .await
+    }
+
+    /// Like `call`, but fails with `CallError::Timeout` if `timeout` completes
+    /// before the response is received. The timeout can be any future (e.g.
+    /// `tokio::time::sleep` or `glib::timeout_future`).
+    async fn call_with_timeout<T, S, V, F>(
+        mut self,
+        method: S,
+        args: V,
+        timeout: F,
+    ) -> CallResponse<T>
+    where
+        T: for<'de> Deserialize<'de> + Any,
+        S: AsRef<str>,
+        V: serde::Serialize,
+        F: Future<Output = ()>,
     {
         let msgid = self.next_msgid();
+        self.call_with_msgid(msgid, method, args, timeout).await
+    }
 
-        let (sender, receiver) = oneshot::channel();
+    /// Like `call_with_timeout`, but with the message id `msgid` reserved
+    /// beforehand with `next_msgid`, so that the call can be referred to while
+    /// it's pending.
+    async fn call_with_msgid<T, S, V, F>(
+        mut self,
+        msgid: u32,
+        method: S,
+        args: V,
+        timeout: F,
+    ) -> CallResponse<T>
+    where
+        T: for<'de> Deserialize<'de> + Any,
+        S: AsRef<str>,
+        V: serde::Serialize,
+        F: Future<Output = ()>,
+    {
+        let (sender, mut receiver) = oneshot::channel();
         self.store_handler(msgid, sender);
 
-        self.write(msgid, method, &args).await?;
+        let _pending = PendingCall {
+            caller: self.clone(),
+            msgid,
+        };
 
-        receiver
-            .map(|res| {
-                let res = res?;
+        if let Err(err) = self.write(msgid, method, &args).await {
+            // If we're already disconnected, report that instead.
+            return match receiver.try_recv() {
+                Ok(Some(Err(err))) => Err(err),
+                _ => Err(err.into()),
+            };
+        }
 
-                if let Some(error) = res.error {
-                    // TODO(ville): Decode the error value.
-                    return Err(CallError::Error(error));
-                }
+        futures::pin_mut!(timeout);
+        let res = match future::select(receiver, timeout).await {
+            future::Either::Left((res, _)) => res??,
+            future::Either::Right(_) => return Err(CallError::Timeout),
+        };
 
-                // The type `()` is special in a sense that it signals voidness of the
-                // returned result. But since the returned result doesn't necessarily exist,
-                // we'll need to handle it our selves.
-                let res = if res.result.is_none() && TypeId::of::<T>() == TypeId::of::<()>() {
-                    rmpv::Value::Nil
-                } else {
-                    res.result.ok_or(CallError::MissingResult)?
-                };
+        if let Some(error) = res.error {
+            return Err(
+                NvimError::decode(error).map_or_else(CallError::DecodeError, CallError::Error)
+            );
+        }
 
-                rmpv::ext::from_value::<T>(res).map_err(|_err| CallError::DecodeResult)
-            })
-            .boxed()
-            .await
+        // The type `()` is special in a sense that it signals voidness of the
+        // returned result. But since the returned result doesn't necessarily exist,
+        // we'll need to handle it our selves.
+        let res = if res.result.is_none() && TypeId::of::<T>() == TypeId::of::<()>() {
+            rmpv::Value::Nil
+        } else {
+            res.result.ok_or(CallError::MissingResult)?
+        };
+
+        // Decode from a reference so that we can keep the original
+        // value around for the error.
+        rmpv::ext::deserialize_from::<T, _>(res.as_ref())
+            .map_err(|error| CallError::DecodeResult { error, value: res })
     }
 
     async fn write<S: AsRef<str>, V: serde::Serialize>(
@@ -102,4 +323,69 @@ where
     fn next_msgid(&mut self) -> u32;
 
     fn store_handler(&mut self, msgid: u32, sender: Sender);
+
+    /// Removes the pending call `msgid`, if any.
+    fn remove_handler(&mut self, msgid: u32) -> Option<Sender>;
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::oneshot;
+
+    use crate::types::ErrorType;
+
+    use super::{CallError, Callbacks, NvimError};
+
+    #[test]
+    fn test_decode_nvim_error() {
+        let value = rmpv::Value::from(vec![
+            rmpv::Value::from(0),
+            rmpv::Value::from("Vim:E492: Not an editor command: foo"),
+        ]);
+        assert_eq!(
+            NvimError::decode(value),
+            Ok(NvimError {
+                kind: ErrorType::Exception,
+                message: String::from("Vim:E492: Not an editor command: foo"),
+            })
+        );
+
+        let value = rmpv::Value::from(vec![
+            rmpv::Value::from(1),
+            rmpv::Value::from("Invalid buffer id: 99"),
+        ]);
+        assert_eq!(
+            NvimError::decode(value).map(|err| err.kind),
+            Ok(ErrorType::Validation)
+        );
+
+        let value = rmpv::Value::from("foo");
+        assert_eq!(NvimError::decode(value.clone()), Err(value));
+    }
+
+    #[test]
+    fn test_callbacks_reconnect() {
+        let mut callbacks = Callbacks::default();
+
+        let (sender, mut pending) = oneshot::channel();
+        callbacks.insert(0, sender);
+        callbacks.disconnect();
+        assert!(matches!(
+            pending.try_recv(),
+            Ok(Some(Err(CallError::Disconnected)))
+        ));
+
+        let (sender, mut during) = oneshot::channel();
+        callbacks.insert(1, sender);
+        assert!(matches!(
+            during.try_recv(),
+            Ok(Some(Err(CallError::Disconnected)))
+        ));
+
+        callbacks.reconnect();
+        let (sender, mut after) = oneshot::channel();
+        callbacks.insert(2, sender);
+        assert!(matches!(after.try_recv(), Ok(None)));
+        assert_eq!(callbacks.len(), 1);
+    }
 }
@@ -0,0 +1,213 @@
+use std::collections::VecDeque;
+
+/// Finds msgpack value boundaries from a buffer without decoding the values.
+///
+/// The scanning is incremental: the progress is kept between the calls to
+/// `next_frame`, so data arriving in many chunks is only scanned once.
+#[derive(Debug)]
+pub(crate) struct FrameScanner {
+    /// How far into the buffer we've scanned.
+    pos: usize,
+    /// Number of values still needed to complete the current frame.
+    pending: u64,
+}
+
+/// Buffer that can be scanned for frames.
+pub(crate) trait Buffer {
+    fn len(&self) -> usize;
+
+    fn get(&self, index: usize) -> Option<u8>;
+}
+
+impl Buffer for VecDeque<u8> {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, index: usize) -> Option<u8> {
+        self.get(index).copied()
+    }
+}
+
+impl Buffer for [u8] {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, index: usize) -> Option<u8> {
+        self.get(index).copied()
+    }
+}
+
+/// Returns the length of the first msgpack value in `buf`, or `None` if the
+/// value is incomplete.
+pub(crate) fn value_len(buf: &[u8]) -> Option<usize> {
+    FrameScanner::default().next_frame(buf)
+}
+
+impl Default for FrameScanner {
+    fn default() -> Self {
+        Self { pos: 0, pending: 1 }
+    }
+}
+
+impl FrameScanner {
+    /// Returns the length of the first complete msgpack value in `buf`, or
+    /// `None` if more data is needed. Once a frame is returned, the caller
+    /// is expected to remove it from the front of the buffer.
+    ///
+    /// Invalid data isn't detected here, but is left for the decoder.
+    pub fn next_frame<B: Buffer + ?Sized>(&mut self, buf: &B) -> Option<usize> {
+        while self.pending > 0 {
+            let marker = buf.get(self.pos)?;
+
+            // Size of the type's length field (in bytes), and the size of the
+            // rest of the header.
+            let (len_size, extra) = match marker {
+                0x00..=0x7f | 0xe0..=0xff | 0xc0..=0xc3 => (0, 0),
+                0x80..=0x8f => {
+                    self.advance(1, 2 * (marker & 0x0f) as u64);
+                    continue;
+                }
+                0x90..=0x9f => {
+                    self.advance(1, (marker & 0x0f) as u64);
+                    continue;
+                }
+                0xa0..=0xbf => {
+                    let len = 1 + (marker & 0x1f) as usize;
+                    if buf.len() < self.pos + len {
+                        return None;
+                    }
+                    self.advance(len, 0);
+                    continue;
+                }
+                0xca => (0, 4),
+                0xcb => (0, 8),
+                0xcc | 0xd0 => (0, 1),
+                0xcd | 0xd1 => (0, 2),
+                0xce | 0xd2 => (0, 4),
+                0xcf | 0xd3 => (0, 8),
+                // fixext, the extra byte is the type.
+                0xd4 => (0, 2),
+                0xd5 => (0, 3),
+                0xd6 => (0, 5),
+                0xd7 => (0, 9),
+                0xd8 => (0, 17),
+                0xc4 | 0xd9 => (1, 0),
+                0xc5 | 0xda => (2, 0),
+                0xc6 | 0xdb => (4, 0),
+                // ext, the extra byte is the type.
+                0xc7 => (1, 1),
+                0xc8 => (2, 1),
+                0xc9 => (4, 1),
+                0xdc | 0xde => {
+                    let n = read_header(buf, self.pos, 2)?;
+                    self.advance(3, if marker == 0xde { 2 * n } else { n });
+                    continue;
+                }
+                0xdd | 0xdf => {
+                    let n = read_header(buf, self.pos, 4)?;
+                    self.advance(5, if marker == 0xdf { 2 * n } else { n });
+                    continue;
+                }
+            };
+
+            let data = if len_size > 0 {
+                read_header(buf, self.pos, len_size)? as usize
+            } else {
+                0
+            };
+
+            let len = 1 + len_size + extra + data;
+            if buf.len() < self.pos + len {
+                return None;
+            }
+
+            self.advance(len, 0);
+        }
+
+        let len = self.pos;
+        *self = Self::default();
+        Some(len)
+    }
+
+    /// Moves past a value of `len` bytes, which contains `children` values.
+    fn advance(&mut self, len: usize, children: u64) {
+        self.pos += len;
+        self.pending = self.pending - 1 + children;
+    }
+}
+
+/// Reads the big endian length field of `n` bytes following the marker at
+/// `pos`.
+fn read_header<B: Buffer + ?Sized>(buf: &B, pos: usize, n: usize) -> Option<u64> {
+    if buf.len() < pos + 1 + n {
+        return None;
+    }
+
+    (pos + 1..pos + 1 + n).try_fold(0, |acc, i| Some((acc << 8) | buf.get(i)? as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::FrameScanner;
+
+    fn encode(value: &rmpv::Value) -> Vec<u8> {
+        let mut buf = Vec::new();
+        rmpv::encode::write_value(&mut buf, value).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_frame_byte_by_byte() {
+        let value = rmpv::Value::from(vec![
+            rmpv::Value::from(2),
+            rmpv::Value::from("redraw"),
+            rmpv::Value::from(vec![
+                rmpv::Value::from(vec![
+                    rmpv::Value::from("grid_line"),
+                    rmpv::Value::from("x".repeat(300)),
+                    rmpv::Value::Binary(vec![1; 70000]),
+                ]),
+                rmpv::Value::Map(vec![
+                    (rmpv::Value::from(-1), rmpv::Value::from(1.5)),
+                    (rmpv::Value::from(u64::MAX), rmpv::Value::Nil),
+                ]),
+                rmpv::Value::Ext(1, vec![1, 2, 3, 4]),
+                rmpv::Value::Ext(2, vec![1; 20]),
+                rmpv::Value::from(vec![rmpv::Value::Nil; 20]),
+            ]),
+        ]);
+        let data = encode(&value);
+
+        let mut scanner = FrameScanner::default();
+        let mut buf = VecDeque::new();
+        for (i, b) in data.iter().enumerate() {
+            buf.push_back(*b);
+            let frame = scanner.next_frame(&buf);
+
+            if i == data.len() - 1 {
+                assert_eq!(frame, Some(data.len()));
+            } else {
+                assert_eq!(frame, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_frame_multiple() {
+        let first = encode(&rmpv::Value::from(vec![rmpv::Value::from(1)]));
+        let second = encode(&rmpv::Value::from("foo"));
+
+        let mut buf = VecDeque::new();
+        buf.extend(&first);
+        buf.extend(&second);
+
+        let mut scanner = FrameScanner::default();
+        assert_eq!(scanner.next_frame(&buf), Some(first.len()));
+        buf.drain(..first.len());
+        assert_eq!(scanner.next_frame(&buf), Some(second.len()));
+    }
+}
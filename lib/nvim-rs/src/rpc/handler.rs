@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use futures::{future::LocalBoxFuture, prelude::*};
+use serde::de::DeserializeOwned;
+
+use crate::rpc::{message::Request, NvimError, RpcWriter, WriteError};
+use crate::types::ErrorType;
+
+/// Handler for the requests and notifications sent by neovim (e.g. through
+/// `rpcrequest()` and `rpcnotify()`).
+#[async_trait::async_trait(?Send)]
+pub trait Handler {
+    /// Handles a request. The returned value is used for the response.
+    async fn handle_request(
+        &self,
+        method: &str,
+        params: rmpv::Value,
+    ) -> Result<rmpv::Value, NvimError>;
+
+    /// Handles a notification. Since notifications have no response, the
+    /// error is only for the caller to report.
+    async fn handle_notification(&self, method: &str, params: rmpv::Value)
+        -> Result<(), NvimError>;
+
+    /// Handles `req` and writes the response to `writer`.
+    async fn respond<W: RpcWriter>(
+        &self,
+        writer: W,
+        req: Request<'_, rmpv::Value>,
+    ) -> Result<(), WriteError> {
+        let res = self.handle_request(&req.method, req.params).await;
+        writer
+            .write_rpc_response(req.msgid, res.as_ref().err(), res.as_ref().ok())
+            .await
+    }
+}
+
+type RequestFn<'a> =
+    Box<dyn Fn(rmpv::Value) -> LocalBoxFuture<'a, Result<rmpv::Value, NvimError>> + 'a>;
+type NotificationFn<'a> =
+    Box<dyn Fn(rmpv::Value) -> LocalBoxFuture<'a, Result<(), NvimError>> + 'a>;
+
+/// Dispatches requests and notifications to functions registered by the
+/// method name. Params are decoded to, and results encoded from, the types
+/// of the registered functions.
+///
+/// Params are always an array, so they should be decoded to a tuple (or a
+/// `Vec`). Unknown methods and params that fail to decode result in an error.
+#[derive(Default)]
+pub struct Dispatcher<'a> {
+    requests: HashMap<String, RequestFn<'a>>,
+    notifications: HashMap<String, NotificationFn<'a>>,
+}
+
+fn decode_params<P: DeserializeOwned>(method: &str, params: rmpv::Value) -> Result<P, NvimError> {
+    rmpv::ext::from_value::<P>(params).map_err(|err| {
+        NvimError::new(
+            ErrorType::Validation,
+            format!("invalid params for {}: {}", method, err),
+        )
+    })
+}
+
+impl<'a> Dispatcher<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `f` to handle requests for `method`.
+    pub fn request<P, R, F, Fut>(&mut self, method: &str, f: F) -> &mut Self
+    where
+        P: DeserializeOwned,
+        R: serde::Serialize,
+        F: Fn(P) -> Fut + 'a,
+        Fut: Future<Output = Result<R, NvimError>> + 'a,
+    {
+        let name = method.to_owned();
+        self.requests.insert(
+            method.to_owned(),
+            Box::new(move |params| match decode_params::<P>(&name, params) {
+                Ok(params) => f(params)
+                    .map(|res| {
+                        res.and_then(|res| {
+                            rmpv::ext::to_value(res).map_err(|err| {
+                                NvimError::new(
+                                    ErrorType::Exception,
+                                    format!("failed to encode result: {}", err),
+                                )
+                            })
+                        })
+                    })
+                    .boxed_local(),
+                Err(err) => future::ready(Err(err)).boxed_local(),
+            }),
+        );
+        self
+    }
+
+    /// Registers `f` to handle notifications for `method`.
+    pub fn notification<P, F, Fut>(&mut self, method: &str, f: F) -> &mut Self
+    where
+        P: DeserializeOwned,
+        F: Fn(P) -> Fut + 'a,
+        Fut: Future<Output = ()> + 'a,
+    {
+        let name = method.to_owned();
+        self.notifications.insert(
+            method.to_owned(),
+            Box::new(move |params| match decode_params::<P>(&name, params) {
+                Ok(params) => f(params).map(Ok).boxed_local(),
+                Err(err) => future::ready(Err(err)).boxed_local(),
+            }),
+        );
+        self
+    }
+}
+
+#[async_trait::async_trait(?Send)]
+impl Handler for Dispatcher<'_> {
+    async fn handle_request(
+        &self,
+        method: &str,
+        params: rmpv::Value,
+    ) -> Result<rmpv::Value, NvimError> {
+        match self.requests.get(method) {
+            Some(f) => f(params).await,
+            None => Err(NvimError::new(
+                ErrorType::Exception,
+                format!("unknown request: {}", method),
+            )),
+        }
+    }
+
+    async fn handle_notification(
+        &self,
+        method: &str,
+        params: rmpv::Value,
+    ) -> Result<(), NvimError> {
+        match self.notifications.get(method) {
+            Some(f) => f(params).await,
+            None => Err(NvimError::new(
+                ErrorType::Exception,
+                format!("unknown notification: {}", method),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::rpc::{message::Request, Handler, NvimError};
+    use crate::types::ErrorType;
+
+    use super::Dispatcher;
+
+    #[test]
+    fn test_dispatch_request() {
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.request("add", |(a, b): (i64, i64)| async move {
+            Ok::<_, NvimError>(a + b)
+        });
+
+        futures::executor::block_on(async {
+            let params = rmpv::Value::from(vec![rmpv::Value::from(1), rmpv::Value::from(2)]);
+            assert_eq!(
+                dispatcher.handle_request("add", params).await,
+                Ok(rmpv::Value::from(3))
+            );
+
+            let params = rmpv::Value::from(vec![rmpv::Value::from("foo")]);
+            assert_eq!(
+                dispatcher
+                    .handle_request("add", params)
+                    .await
+                    .map_err(|err| err.kind),
+                Err(ErrorType::Validation)
+            );
+
+            assert_eq!(
+                dispatcher
+                    .handle_request("sub", rmpv::Value::Array(vec![]))
+                    .await
+                    .map_err(|err| err.kind),
+                Err(ErrorType::Exception)
+            );
+        });
+    }
+
+    #[test]
+    fn test_dispatch_notification() {
+        let called = Cell::new(false);
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.notification("ping", |_: Vec<rmpv::Value>| async {
+            called.set(true);
+        });
+
+        futures::executor::block_on(async {
+            dispatcher
+                .handle_notification("ping", rmpv::Value::Array(vec![]))
+                .await
+                .unwrap();
+        });
+
+        assert!(called.get());
+    }
+
+    #[test]
+    fn test_respond_error() {
+        let dispatcher = Dispatcher::new();
+        let mut buf = Vec::new();
+
+        futures::executor::block_on(async {
+            dispatcher
+                .respond(&mut buf, Request::new(7, "foo", rmpv::Value::Array(vec![])))
+                .await
+                .unwrap();
+        });
+
+        let res = rmpv::decode::read_value(&mut buf.as_slice()).unwrap();
+        assert_eq!(
+            res,
+            rmpv::Value::from(vec![
+                rmpv::Value::from(1),
+                rmpv::Value::from(7),
+                rmpv::Value::from(vec![
+                    rmpv::Value::from(0),
+                    rmpv::Value::from("unknown request: foo"),
+                ]),
+                rmpv::Value::Nil,
+            ])
+        );
+    }
+}
@@ -1,9 +1,15 @@
 pub mod caller;
+mod frame;
+pub mod handler;
 pub mod message;
 pub mod reader;
+pub mod record;
+pub mod redraw;
 pub mod writer;
 
-pub use caller::{CallError, CallResponse, Caller, HandleError};
+pub use caller::{CallError, CallResponse, Caller, HandleError, NvimError};
+pub use handler::{Dispatcher, Handler};
 pub use message::Message;
-pub use reader::{ReadError, RpcReader};
+pub use reader::{LazyMessage, ReadError, RpcReader};
+pub use redraw::{GridLineRef, GridLineVisitor, Redraw, RedrawEvent};
 pub use writer::{RpcWriter, WriteError};
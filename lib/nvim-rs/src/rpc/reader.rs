@@ -2,7 +2,7 @@ use std::{collections::VecDeque, io};
 
 use futures::prelude::*;
 
-use super::message::Message;
+use super::{frame::FrameScanner, message::Message, redraw::Redraw};
 
 /// Cursor implementing non-destructive read for `VecDeque`.
 pub(crate) struct Cursor<'a> {
@@ -65,12 +65,40 @@ impl std::fmt::Display for ReadError {
     }
 }
 
+/// Message from `RpcReader::recv_lazy`.
+#[derive(Debug)]
+pub enum LazyMessage {
+    /// A `redraw` notification, left undecoded.
+    Redraw(Redraw),
+    Message(Message),
+}
+
+/// Start of an encoded `redraw` notification, i.e. `[2, "redraw", ...]`.
+const REDRAW_PREFIX: &[u8] = b"\x93\x02\xa6redraw";
+
+impl LazyMessage {
+    /// Decodes a single message from `data`, like `RpcReader::recv_lazy`.
+    pub fn from_slice(data: &[u8]) -> Result<Self, ReadError> {
+        match data.strip_prefix(REDRAW_PREFIX) {
+            Some(params) => Ok(LazyMessage::Redraw(Redraw::new(params.to_vec()))),
+            None => rmp_serde::from_slice(data)
+                .map(LazyMessage::Message)
+                .map_err(ReadError::RmpError),
+        }
+    }
+}
+
+/// Callback for `RpcReader::set_tee`.
+type Tee = Box<dyn FnMut(&[u8])>;
+
 pub struct RpcReader<R>
 where
     R: AsyncRead + Unpin,
 {
     reader: futures::io::BufReader<R>,
     buf: VecDeque<u8>,
+    scanner: FrameScanner,
+    tee: Option<Tee>,
 }
 
 impl<R> RpcReader<R>
@@ -81,9 +109,17 @@ where
         Self {
             reader: futures::io::BufReader::new(reader),
             buf: VecDeque::new(),
+            scanner: FrameScanner::default(),
+            tee: None,
         }
     }
 
+    /// Sets a callback that gets the raw data of every message read, before
+    /// it's decoded.
+    pub fn set_tee<F: FnMut(&[u8]) + 'static>(&mut self, f: F) {
+        self.tee = Some(Box::new(f));
+    }
+
     pub fn into_inner(self) -> R {
         self.reader.into_inner()
     }
@@ -107,32 +143,53 @@ where
         }
     }
 
-    pub async fn recv(&mut self) -> Result<Message, ReadError> {
+    /// Waits until we have a complete message in the buffer, so that we
+    /// only decode it once. Returns the message's length.
+    async fn next_frame(&mut self) -> Result<usize, ReadError> {
         loop {
-            let mut cursor = Cursor::new(&self.buf);
-
-            // Try decoding value from the buffer's current content.
-            match rmp_serde::from_read::<_, Message>(&mut cursor) {
-                Ok(val) => {
-                    // All good, there was enough data. Drop the read data.
-                    self.buf.drain(..cursor.pos);
+            match self.scanner.next_frame(&self.buf) {
+                Some(len) => {
+                    if let Some(ref mut tee) = self.tee {
+                        tee(&self.buf.make_contiguous()[..len]);
+                    }
 
-                    return Ok(val);
-                }
-                // If we got an UnexpectedEof error, try reading more data into
-                // the buffer.
-                Err(rmp_serde::decode::Error::InvalidMarkerRead(err))
-                | Err(rmp_serde::decode::Error::InvalidDataRead(err))
-                    if err.kind() == io::ErrorKind::UnexpectedEof =>
-                {
-                    self.fill_buffer().await?
-                }
-                Err(err) => {
-                    return Err(ReadError::RmpError(err));
+                    return Ok(len);
                 }
+                None => self.fill_buffer().await?,
             }
         }
     }
+
+    pub async fn recv(&mut self) -> Result<Message, ReadError> {
+        let len = self.next_frame().await?;
+
+        let mut cursor = Cursor::new(&self.buf);
+        let res = rmp_serde::from_read::<_, Message>(&mut cursor);
+
+        // Drop the message's data, even if it failed to decode.
+        self.buf.drain(..len);
+
+        res.map_err(ReadError::RmpError)
+    }
+
+    /// Like `recv`, but `redraw` notifications are returned undecoded, so
+    /// that they can be decoded without any intermediate values.
+    pub async fn recv_lazy(&mut self) -> Result<LazyMessage, ReadError> {
+        let len = self.next_frame().await?;
+
+        if len >= REDRAW_PREFIX.len() && self.buf.iter().take(REDRAW_PREFIX.len()).eq(REDRAW_PREFIX)
+        {
+            self.buf.drain(..REDRAW_PREFIX.len());
+            let params = self.buf.drain(..len - REDRAW_PREFIX.len()).collect();
+            return Ok(LazyMessage::Redraw(Redraw::new(params)));
+        }
+
+        let mut cursor = Cursor::new(&self.buf);
+        let res = rmp_serde::from_read::<_, Message>(&mut cursor);
+        self.buf.drain(..len);
+
+        res.map(LazyMessage::Message).map_err(ReadError::RmpError)
+    }
 }
 
 impl<R> From<R> for RpcReader<R>
@@ -152,6 +209,51 @@ mod tests {
     };
 
     use super::Cursor;
+
+    #[test]
+    fn test_tee() {
+        use std::{cell::RefCell, rc::Rc};
+
+        use crate::rpc::{message::Notification, LazyMessage, RpcReader, RpcWriter};
+
+        let first = <Vec<u8> as RpcWriter>::encode(&Notification::new(
+            "redraw",
+            rmpv::Value::Array(vec![]),
+        ))
+        .unwrap();
+        let second =
+            <Vec<u8> as RpcWriter>::encode(&Notification::new("foo", rmpv::Value::Array(vec![])))
+                .unwrap();
+
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut reader = RpcReader::new(futures::io::Cursor::new(
+            [first.clone(), second.clone()].concat(),
+        ));
+        reader.set_tee({
+            let seen = seen.clone();
+            move |data| seen.borrow_mut().push(data.to_vec())
+        });
+
+        futures::executor::block_on(async {
+            assert!(matches!(
+                reader.recv_lazy().await,
+                Ok(LazyMessage::Redraw(_))
+            ));
+            assert!(reader.recv().await.is_ok());
+        });
+
+        assert_eq!(*seen.borrow(), vec![first.clone(), second.clone()]);
+
+        assert!(matches!(
+            LazyMessage::from_slice(&first),
+            Ok(LazyMessage::Redraw(_))
+        ));
+        assert!(matches!(
+            LazyMessage::from_slice(&second),
+            Ok(LazyMessage::Message(_))
+        ));
+    }
+
     #[test]
     fn test_reads_front_and_back_at_once() {
         let mut dq: VecDeque<u8> = VecDeque::from_iter(0..6);
@@ -0,0 +1,176 @@
+//! Session recordings: a log of the raw msgpack-rpc messages of a session.
+//!
+//! The log is a sequence of msgpack arrays, one per message:
+//! `[time, direction, data]`, where `time` is microseconds since the start
+//! of the recording, `direction` is `0` for messages from neovim and `1` for
+//! messages to neovim, and `data` is the message as binary.
+
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From neovim.
+    Incoming,
+    /// To neovim.
+    Outgoing,
+}
+
+impl Direction {
+    fn to_u8(self) -> u8 {
+        match self {
+            Direction::Incoming => 0,
+            Direction::Outgoing => 1,
+        }
+    }
+
+    fn from_u8(v: u8) -> Option<Self> {
+        match v {
+            0 => Some(Direction::Incoming),
+            1 => Some(Direction::Outgoing),
+            _ => None,
+        }
+    }
+}
+
+/// A recorded message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Time since the start of the recording.
+    pub time: Duration,
+    pub direction: Direction,
+    /// The encoded message.
+    pub data: Vec<u8>,
+}
+
+fn invalid<E: std::fmt::Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn write_entry<W: io::Write>(
+    w: &mut W,
+    time: Duration,
+    direction: Direction,
+    data: &[u8],
+) -> io::Result<()> {
+    rmp::encode::write_array_len(w, 3)?;
+    rmp::encode::write_uint(w, time.as_micros() as u64)?;
+    rmp::encode::write_uint(w, direction.to_u8() as u64)?;
+    rmp::encode::write_bin(w, data)?;
+    Ok(())
+}
+
+impl Entry {
+    pub fn write_to<W: io::Write>(&self, w: &mut W) -> io::Result<()> {
+        write_entry(w, self.time, self.direction, &self.data)
+    }
+
+    /// Reads the next entry. Returns `None` at the end of the log.
+    pub fn read_from<R: io::Read>(r: &mut R) -> io::Result<Option<Self>> {
+        let len = match rmp::decode::read_array_len(r) {
+            Ok(len) => len,
+            Err(rmp::decode::ValueReadError::InvalidMarkerRead(err))
+                if err.kind() == io::ErrorKind::UnexpectedEof =>
+            {
+                return Ok(None)
+            }
+            Err(err) => return Err(invalid(err)),
+        };
+
+        if len != 3 {
+            return Err(invalid(format!("invalid entry length: {}", len)));
+        }
+
+        let time = rmp::decode::read_int::<u64, _>(r).map_err(invalid)?;
+        let direction = rmp::decode::read_int::<u8, _>(r).map_err(invalid)?;
+        let direction = Direction::from_u8(direction)
+            .ok_or_else(|| invalid(format!("invalid direction: {}", direction)))?;
+
+        let n = rmp::decode::read_bin_len(r).map_err(invalid)?;
+        let mut data = vec![0; n as usize];
+        r.read_exact(&mut data)?;
+
+        Ok(Some(Self {
+            time: Duration::from_micros(time),
+            direction,
+            data,
+        }))
+    }
+}
+
+/// Writes messages to a session log, timestamped from the recorder's
+/// creation.
+#[derive(Debug)]
+pub struct Recorder<W: io::Write> {
+    writer: W,
+    start: Instant,
+}
+
+impl<W: io::Write> Recorder<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            start: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, direction: Direction, data: &[u8]) -> io::Result<()> {
+        write_entry(&mut self.writer, self.start.elapsed(), direction, data)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads all entries from a session log.
+pub fn read_entries<R: io::Read>(mut r: R) -> impl Iterator<Item = io::Result<Entry>> {
+    std::iter::from_fn(move || Entry::read_from(&mut r).transpose())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{read_entries, Direction, Entry, Recorder};
+
+    #[test]
+    fn test_record_and_read() {
+        let mut recorder = Recorder::new(Vec::new());
+        recorder.record(Direction::Outgoing, &[0x90]).unwrap();
+        recorder.record(Direction::Incoming, &[1; 300]).unwrap();
+        let data = recorder.into_inner();
+
+        let entries = read_entries(data.as_slice())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].direction, Direction::Outgoing);
+        assert_eq!(entries[0].data, vec![0x90]);
+        assert_eq!(entries[1].direction, Direction::Incoming);
+        assert_eq!(entries[1].data, vec![1; 300]);
+        assert!(entries[0].time <= entries[1].time);
+    }
+
+    #[test]
+    fn test_read_truncated() {
+        let mut data = Vec::new();
+        Entry {
+            time: Duration::from_millis(5),
+            direction: Direction::Incoming,
+            data: vec![1, 2, 3],
+        }
+        .write_to(&mut data)
+        .unwrap();
+        data.pop();
+
+        let mut entries = read_entries(data.as_slice());
+        assert!(entries.next().unwrap().is_err());
+    }
+}
@@ -0,0 +1,329 @@
+//! Decoding of the `redraw` notification straight from the msgpack data.
+//!
+//! `grid_line` events make up most of the redraw data, so instead of
+//! decoding them into owned values, their cells are streamed to a
+//! `GridLineVisitor`. Other events are decoded into `UiEvent`s as usual.
+
+use crate::rpc::frame::value_len;
+use crate::types::UiEvent;
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The msgpack data was invalid or didn't have the expected shape.
+    Invalid(String),
+    /// Decoding an event failed.
+    Event(rmp_serde::decode::Error),
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::Invalid(err) => write!(f, "invalid redraw data: {}", err),
+            DecodeError::Event(err) => write!(f, "failed to decode event: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+fn invalid<E: std::fmt::Display>(err: E) -> DecodeError {
+    DecodeError::Invalid(err.to_string())
+}
+
+fn read_array_len(buf: &mut &[u8]) -> Result<usize, DecodeError> {
+    rmp::decode::read_array_len(buf)
+        .map(|len| len as usize)
+        .map_err(invalid)
+}
+
+fn read_int(buf: &mut &[u8]) -> Result<i64, DecodeError> {
+    rmp::decode::read_int(buf).map_err(invalid)
+}
+
+fn read_str<'a>(buf: &mut &'a [u8]) -> Result<&'a str, DecodeError> {
+    let (s, rest) = rmp::decode::read_str_from_slice(*buf).map_err(invalid)?;
+    *buf = rest;
+    Ok(s)
+}
+
+/// Splits the next msgpack value from `buf`.
+fn skip<'a>(buf: &mut &'a [u8]) -> Result<&'a [u8], DecodeError> {
+    let len = value_len(buf).ok_or_else(|| invalid("unexpected end of data"))?;
+    let (value, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(value)
+}
+
+/// Receives the cells of a `grid_line` event.
+pub trait GridLineVisitor {
+    /// Called for each cell (in order). The `hl_id` is `None` if it's the same
+    /// as on the previous cell, and `repeat` is `None` if it's one.
+    fn cell(&mut self, text: &str, hl_id: Option<i64>, repeat: Option<i64>);
+}
+
+/// A `grid_line` event, borrowed from the redraw data.
+#[derive(Debug, Clone, Copy)]
+pub struct GridLineRef<'a> {
+    pub grid: i64,
+    pub row: i64,
+    pub col_start: i64,
+    pub wrap: bool,
+    cells: &'a [u8],
+}
+
+impl<'a> GridLineRef<'a> {
+    fn decode(buf: &mut &'a [u8]) -> Result<Self, DecodeError> {
+        let len = read_array_len(buf)?;
+        if len < 4 {
+            return Err(invalid(format!("too short grid_line event: {}", len)));
+        }
+
+        let grid = read_int(buf)?;
+        let row = read_int(buf)?;
+        let col_start = read_int(buf)?;
+        let cells = skip(buf)?;
+        let wrap = if len > 4 {
+            rmp::decode::read_bool(buf).map_err(invalid)?
+        } else {
+            false
+        };
+
+        // Ignore any fields added in the future.
+        for _ in 5..len {
+            skip(buf)?;
+        }
+
+        Ok(Self {
+            grid,
+            row,
+            col_start,
+            wrap,
+            cells,
+        })
+    }
+
+    /// Passes the cells to `visitor`.
+    pub fn visit_cells<V: GridLineVisitor + ?Sized>(
+        &self,
+        visitor: &mut V,
+    ) -> Result<(), DecodeError> {
+        let mut buf = self.cells;
+        for _ in 0..read_array_len(&mut buf)? {
+            let len = read_array_len(&mut buf)?;
+            if len == 0 {
+                return Err(invalid("empty grid_line cell"));
+            }
+
+            let text = read_str(&mut buf)?;
+            let hl_id = if len > 1 {
+                Some(read_int(&mut buf)?)
+            } else {
+                None
+            };
+            let repeat = if len > 2 {
+                Some(read_int(&mut buf)?)
+            } else {
+                None
+            };
+            for _ in 3..len {
+                skip(&mut buf)?;
+            }
+
+            visitor.cell(text, hl_id, repeat);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum RedrawEvent<'a> {
+    GridLine(GridLineRef<'a>),
+    /// Any other event than `grid_line`.
+    Event(UiEvent),
+}
+
+/// The undecoded params of a `redraw` notification.
+#[derive(Debug)]
+pub struct Redraw {
+    params: Vec<u8>,
+}
+
+impl Redraw {
+    pub fn new(params: Vec<u8>) -> Self {
+        Self { params }
+    }
+
+    /// The raw msgpack data of the params.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.params
+    }
+
+    /// Iterates over the events. Once an error is returned, the iteration
+    /// stops.
+    pub fn events(&self) -> RedrawEvents<'_> {
+        RedrawEvents {
+            buf: &self.params,
+            batches: None,
+            grid_lines: 0,
+        }
+    }
+}
+
+/// Iterator over the events of `Redraw`.
+pub struct RedrawEvents<'a> {
+    buf: &'a [u8],
+    /// Number of event batches left, read on the first call to `next`.
+    batches: Option<usize>,
+    /// Number of `grid_line` events left in the current batch.
+    grid_lines: usize,
+}
+
+impl<'a> RedrawEvents<'a> {
+    fn next_event(&mut self) -> Result<Option<RedrawEvent<'a>>, DecodeError> {
+        if self.grid_lines > 0 {
+            self.grid_lines -= 1;
+            return GridLineRef::decode(&mut self.buf)
+                .map(|event| Some(RedrawEvent::GridLine(event)));
+        }
+
+        let batches = match self.batches {
+            Some(ref mut batches) => batches,
+            None => self.batches.insert(read_array_len(&mut self.buf)?),
+        };
+
+        if *batches == 0 {
+            return Ok(None);
+        }
+        *batches -= 1;
+
+        // Peek the event's name.
+        let mut peek = self.buf;
+        let len = read_array_len(&mut peek)?;
+        if len > 0 && read_str(&mut peek)? == "grid_line" {
+            self.buf = peek;
+            self.grid_lines = len - 1;
+            return self.next_event();
+        }
+
+        let batch = skip(&mut self.buf)?;
+        rmp_serde::from_slice::<UiEvent>(batch)
+            .map(|event| Some(RedrawEvent::Event(event)))
+            .map_err(DecodeError::Event)
+    }
+}
+
+impl<'a> Iterator for RedrawEvents<'a> {
+    type Item = Result<RedrawEvent<'a>, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Ok(event) => event.map(Ok),
+            Err(err) => {
+                // Stop the iteration.
+                self.buf = &[];
+                self.batches = Some(0);
+                self.grid_lines = 0;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rpc::{message::Notification, LazyMessage, RpcReader, RpcWriter};
+    use crate::types::UiEvent;
+
+    use super::{GridLineVisitor, RedrawEvent};
+
+    #[derive(Default)]
+    struct Cells(Vec<(String, Option<i64>, Option<i64>)>);
+
+    impl GridLineVisitor for Cells {
+        fn cell(&mut self, text: &str, hl_id: Option<i64>, repeat: Option<i64>) {
+            self.0.push((text.to_owned(), hl_id, repeat));
+        }
+    }
+
+    fn v<T: Into<rmpv::Value>>(v: T) -> rmpv::Value {
+        v.into()
+    }
+
+    #[test]
+    fn test_redraw_events() {
+        let params = v(vec![
+            v(vec![
+                v("grid_line"),
+                v(vec![
+                    v(1),
+                    v(2),
+                    v(3),
+                    v(vec![
+                        v(vec![v("a"), v(5)]),
+                        v(vec![v("b")]),
+                        v(vec![v(" "), v(0), v(300)]),
+                    ]),
+                    v(false),
+                ]),
+                v(vec![
+                    v(2),
+                    v(0),
+                    v(0),
+                    v(vec![v(vec![v("c"), v(1)])]),
+                    v(true),
+                ]),
+            ]),
+            v(vec![v("grid_cursor_goto"), v(vec![v(1), v(2), v(3)])]),
+            v(vec![v("flush"), v(Vec::<rmpv::Value>::new())]),
+        ]);
+        let data = <Vec<u8> as RpcWriter>::encode(&Notification::new("redraw", params)).unwrap();
+
+        let mut reader = RpcReader::new(futures::io::Cursor::new(data));
+        let redraw = match futures::executor::block_on(reader.recv_lazy()).unwrap() {
+            LazyMessage::Redraw(redraw) => redraw,
+            msg => panic!("unexpected message: {:?}", msg),
+        };
+
+        let events = redraw.events().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(events.len(), 4);
+
+        match events[0] {
+            RedrawEvent::GridLine(line) => {
+                assert_eq!(
+                    (line.grid, line.row, line.col_start, line.wrap),
+                    (1, 2, 3, false)
+                );
+
+                let mut cells = Cells::default();
+                line.visit_cells(&mut cells).unwrap();
+                assert_eq!(
+                    cells.0,
+                    vec![
+                        (String::from("a"), Some(5), None),
+                        (String::from("b"), None, None),
+                        (String::from(" "), Some(0), Some(300)),
+                    ]
+                );
+            }
+            ref event => panic!("unexpected event: {:?}", event),
+        }
+
+        assert!(matches!(events[1], RedrawEvent::GridLine(line) if line.grid == 2 && line.wrap));
+        assert!(matches!(
+            events[2],
+            RedrawEvent::Event(UiEvent::GridCursorGoto(_))
+        ));
+        assert!(matches!(events[3], RedrawEvent::Event(UiEvent::Flush)));
+    }
+
+    #[test]
+    fn test_other_messages() {
+        let data =
+            <Vec<u8> as RpcWriter>::encode(&Notification::new("gnvim", v(vec![v(1)]))).unwrap();
+
+        let mut reader = RpcReader::new(futures::io::Cursor::new(data));
+        let msg = futures::executor::block_on(reader.recv_lazy()).unwrap();
+        assert!(matches!(msg, LazyMessage::Message(_)));
+    }
+}
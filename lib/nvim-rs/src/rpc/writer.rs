@@ -10,6 +10,24 @@ pub enum WriteError {
     IO(io::Error),
 }
 
+impl std::fmt::Display for WriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::RmpSerde(err) => write!(f, "rmp serde error: {}", err),
+            WriteError::IO(err) => write!(f, "io error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WriteError::RmpSerde(err) => Some(err),
+            WriteError::IO(err) => Some(err),
+        }
+    }
+}
+
 impl From<rmp_serde::encode::Error> for WriteError {
     fn from(err: rmp_serde::encode::Error) -> Self {
         Self::RmpSerde(err)
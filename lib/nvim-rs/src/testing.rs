@@ -0,0 +1,310 @@
+//! In-process fake neovim, for tests that can't rely on a real `nvim`.
+//!
+//! ```ignore
+//! let mut nvim = FakeNeovim::new();
+//! nvim.respond("nvim_get_vvar", "foo");
+//!
+//! let (server, reader, writer) = nvim.start();
+//! let client = Client::new(writer);
+//! let mut reader = RpcReader::new(reader);
+//! ```
+//!
+//! The server is driven by `FakeServer::run`, which needs to be polled along
+//! side the client.
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+use futures::{channel::oneshot, prelude::*};
+
+use crate::rpc::{
+    message::{Message, Notification},
+    Dispatcher, Handler, NvimError, ReadError, RpcReader, RpcWriter,
+};
+
+#[derive(Debug, Default)]
+struct PipeState {
+    buf: VecDeque<u8>,
+    writers: usize,
+    waker: Option<Waker>,
+}
+
+impl PipeState {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// Read end of `pipe`.
+#[derive(Debug)]
+pub struct PipeReader(Rc<RefCell<PipeState>>);
+
+/// Write end of `pipe`. Writes never block. The pipe is closed once all
+/// writers are dropped (or closed).
+#[derive(Debug)]
+pub struct PipeWriter {
+    state: Rc<RefCell<PipeState>>,
+    closed: bool,
+}
+
+/// Creates an in-memory, single threaded pipe.
+pub fn pipe() -> (PipeWriter, PipeReader) {
+    let state = Rc::new(RefCell::new(PipeState {
+        writers: 1,
+        ..Default::default()
+    }));
+
+    (
+        PipeWriter {
+            state: state.clone(),
+            closed: false,
+        },
+        PipeReader(state),
+    )
+}
+
+impl AsyncRead for PipeReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut state = self.0.borrow_mut();
+
+        if state.buf.is_empty() {
+            if state.writers == 0 {
+                return Poll::Ready(Ok(0));
+            }
+
+            state.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let n = buf.len().min(state.buf.len());
+        for (dst, src) in buf.iter_mut().zip(state.buf.drain(..n)) {
+            *dst = src;
+        }
+
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl PipeWriter {
+    fn push(&self, buf: &[u8]) -> io::Result<()> {
+        if self.closed {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+
+        let mut state = self.state.borrow_mut();
+        state.buf.extend(buf);
+        state.wake();
+
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        if !self.closed {
+            self.closed = true;
+
+            let mut state = self.state.borrow_mut();
+            state.writers -= 1;
+            state.wake();
+        }
+    }
+}
+
+impl Clone for PipeWriter {
+    fn clone(&self) -> Self {
+        self.state.borrow_mut().writers += 1;
+        Self {
+            state: self.state.clone(),
+            closed: false,
+        }
+    }
+}
+
+impl Drop for PipeWriter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl AsyncWrite for PipeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.push(buf).map(|_| buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.shutdown();
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Builder for the fake neovim. Requests without a registered response get
+/// an error response.
+#[derive(Default)]
+pub struct FakeNeovim {
+    dispatcher: Dispatcher<'static>,
+    redraws: HashMap<String, Vec<rmpv::Value>>,
+}
+
+impl FakeNeovim {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Responds to `method` with `result`.
+    pub fn respond<R>(&mut self, method: &str, result: R) -> &mut Self
+    where
+        R: serde::Serialize + Clone + 'static,
+    {
+        self.dispatcher.request(method, move |_: Vec<rmpv::Value>| {
+            future::ready(Ok::<_, NvimError>(result.clone()))
+        });
+        self
+    }
+
+    /// Responds to `method` with the result of `f`.
+    pub fn on_request<P, R, F>(&mut self, method: &str, f: F) -> &mut Self
+    where
+        P: serde::de::DeserializeOwned + 'static,
+        R: serde::Serialize + 'static,
+        F: Fn(P) -> Result<R, NvimError> + 'static,
+    {
+        self.dispatcher
+            .request(method, move |params| future::ready(f(params)));
+        self
+    }
+
+    /// Sends a `redraw` notification with `batches` after responding to
+    /// `method` (e.g. `nvim_ui_attach`).
+    pub fn redraw_after(&mut self, method: &str, batches: Vec<rmpv::Value>) -> &mut Self {
+        self.redraws.insert(method.to_owned(), batches);
+        self
+    }
+
+    /// Starts the server. Returns the server, and the reader and writer for
+    /// the client.
+    pub fn start(self) -> (FakeServer, PipeReader, PipeWriter) {
+        let (client_writer, server_reader) = pipe();
+        let (server_writer, client_reader) = pipe();
+
+        let (close_tx, close_rx) = oneshot::channel();
+
+        let server = FakeServer {
+            nvim: self,
+            reader: RefCell::new(Some((RpcReader::new(server_reader), close_rx))),
+            writer: RefCell::new(Some(server_writer)),
+            close: RefCell::new(Some(close_tx)),
+            received: RefCell::new(Vec::new()),
+        };
+
+        (server, client_reader, client_writer)
+    }
+}
+
+/// Running fake neovim, see `FakeNeovim`.
+pub struct FakeServer {
+    nvim: FakeNeovim,
+    reader: RefCell<Option<(RpcReader<PipeReader>, oneshot::Receiver<()>)>>,
+    writer: RefCell<Option<PipeWriter>>,
+    /// Stops `run` once triggered (or dropped).
+    close: RefCell<Option<oneshot::Sender<()>>>,
+    received: RefCell<Vec<(String, rmpv::Value)>>,
+}
+
+impl FakeServer {
+    /// Serves the client until either end closes the connection.
+    pub async fn run(&self) -> Result<(), ReadError> {
+        let (mut reader, mut close) = self.reader.take().expect("server already running");
+
+        loop {
+            let recv = match future::select(Box::pin(reader.recv()), &mut close).await {
+                future::Either::Left((recv, _)) => recv,
+                future::Either::Right(_) => return Ok(()),
+            };
+
+            let msg = match recv {
+                Ok(msg) => msg,
+                Err(ReadError::IOError(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(())
+                }
+                Err(err) => return Err(err),
+            };
+
+            match msg {
+                Message::Request(req) => {
+                    let method = req.method.to_string();
+                    self.received
+                        .borrow_mut()
+                        .push((method.clone(), req.params.clone()));
+
+                    // If we're closed, the client won't get the response.
+                    let Some(writer) = self.writer() else {
+                        continue;
+                    };
+                    // Writing to the pipe can't fail while it's open.
+                    let _ = self.nvim.dispatcher.respond(writer, req).await;
+
+                    if let Some(batches) = self.nvim.redraws.get(&method) {
+                        self.redraw(batches.clone());
+                    }
+                }
+                Message::Notification(notification) => {
+                    self.received
+                        .borrow_mut()
+                        .push((notification.method.to_string(), notification.params));
+                }
+                Message::Response(_) => {}
+            }
+        }
+    }
+
+    fn writer(&self) -> Option<PipeWriter> {
+        self.writer.borrow().clone()
+    }
+
+    /// Sends a notification to the client.
+    pub fn notify(&self, method: &str, params: rmpv::Value) {
+        if let Some(writer) = self.writer() {
+            let data = <PipeWriter as RpcWriter>::encode(&Notification::new(method, params))
+                .expect("failed to encode notification");
+            // Can't fail while the pipe is open.
+            let _ = writer.push(&data);
+        }
+    }
+
+    /// Sends a `redraw` notification with `batches` (e.g. `["flush", []]`).
+    pub fn redraw(&self, batches: Vec<rmpv::Value>) {
+        self.notify("redraw", rmpv::Value::from(batches));
+    }
+
+    /// Requests (and notifications) received from the client, as method and
+    /// params pairs.
+    pub fn received(&self) -> Vec<(String, rmpv::Value)> {
+        self.received.borrow().clone()
+    }
+
+    /// Closes the connection from the server's side, i.e. the client will
+    /// see EOF once it has read the pending data, and `run` returns.
+    pub fn close(&self) {
+        self.writer.borrow_mut().take();
+        self.close.borrow_mut().take();
+    }
+}
@@ -1706,7 +1706,7 @@ impl<'de> serde::Deserialize<'de> for MsgShow {
 }
 #[derive(Debug)]
 pub struct MsgShowcmd {
-    pub content: Vec<rmpv::Value>,
+    pub content: Vec<MsgShowContent>,
 }
 impl<'de> serde::Deserialize<'de> for MsgShowcmd {
     fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
@@ -1734,7 +1734,7 @@ impl<'de> serde::Deserialize<'de> for MsgShowcmd {
 }
 #[derive(Debug)]
 pub struct MsgShowmode {
-    pub content: Vec<rmpv::Value>,
+    pub content: Vec<MsgShowContent>,
 }
 impl<'de> serde::Deserialize<'de> for MsgShowmode {
     fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
@@ -1762,7 +1762,7 @@ impl<'de> serde::Deserialize<'de> for MsgShowmode {
 }
 #[derive(Debug)]
 pub struct MsgRuler {
-    pub content: Vec<rmpv::Value>,
+    pub content: Vec<MsgShowContent>,
 }
 impl<'de> serde::Deserialize<'de> for MsgRuler {
     fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
@@ -1848,6 +1848,32 @@ impl<'de> serde::Deserialize<'de> for ErrorExit {
         d.deserialize_any(Visitor)
     }
 }
+#[doc = r" Type of an error response from neovim."]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorType {
+    Exception,
+    Validation,
+    #[doc = r" Error type not known to us."]
+    Unknown(i64),
+}
+impl From<i64> for ErrorType {
+    fn from(id: i64) -> Self {
+        match id {
+            0i64 => Self::Exception,
+            1i64 => Self::Validation,
+            id => Self::Unknown(id),
+        }
+    }
+}
+impl From<ErrorType> for i64 {
+    fn from(kind: ErrorType) -> Self {
+        match kind {
+            ErrorType::Exception => 0i64,
+            ErrorType::Validation => 1i64,
+            ErrorType::Unknown(id) => id,
+        }
+    }
+}
 #[derive(Debug)]
 pub enum UiEvent {
     ModeInfoSet(Vec<ModeInfoSet>),
@@ -1919,6 +1945,11 @@ pub enum UiEvent {
     MsgRuler(Vec<MsgRuler>),
     MsgHistoryShow(Vec<MsgHistoryShow>),
     ErrorExit(Vec<ErrorExit>),
+    #[doc = r" Event not known to us (e.g. from newer version of neovim)."]
+    Unknown {
+        name: String,
+        args: Vec<rmpv::Value>,
+    },
 }
 impl Display for UiEvent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -1992,6 +2023,7 @@ impl Display for UiEvent {
             Self::MsgRuler(_) => write!(f, "msg_ruler"),
             Self::MsgHistoryShow(_) => write!(f, "msg_history_show"),
             Self::ErrorExit(_) => write!(f, "error_exit"),
+            Self::Unknown { name, .. } => write!(f, "{}", name),
         }
     }
 }
@@ -2134,7 +2166,10 @@ impl<'de> serde::Deserialize<'de> for UiEvent {
                     "msg_ruler" => UiEvent::MsgRuler(seq_to_vec!(seq)),
                     "msg_history_show" => UiEvent::MsgHistoryShow(seq_to_vec!(seq)),
                     "error_exit" => UiEvent::ErrorExit(seq_to_vec!(seq)),
-                    v => panic!("failed to decode message {:?}", v),
+                    v => UiEvent::Unknown {
+                        name: v.to_owned(),
+                        args: seq_to_vec!(seq),
+                    },
                 })
             }
         }
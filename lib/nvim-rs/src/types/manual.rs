@@ -190,18 +190,27 @@ pub struct PopupmenuItem {
 pub struct MsgShowContent {
     pub attr_id: i64,
     pub text_chunk: String,
+    /// Only sent by newer versions of neovim.
+    #[serde(default)]
+    pub hl_id: i64,
 }
 
 #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
 pub struct MsgHistoryShowEntry {
     pub kind: String,
     pub content: Vec<MsgHistoryShowContent>,
+    /// Only sent by newer versions of neovim.
+    #[serde(default)]
+    pub append: bool,
 }
 
 #[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
 pub struct MsgHistoryShowContent {
     pub attr_id: i64,
     pub text_chunk: String,
+    /// Only sent by newer versions of neovim.
+    #[serde(default)]
+    pub hl_id: i64,
 }
 
 #[derive(Debug, serde::Deserialize, serde::Serialize)]
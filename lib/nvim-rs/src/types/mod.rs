@@ -7,4 +7,4 @@ pub mod uievents {
     pub use super::gen::*;
 }
 
-pub use uievents::UiEvent;
+pub use uievents::{ErrorType, UiEvent};
@@ -1,8 +1,10 @@
 use std::rc::Rc;
+use std::time::Duration;
 
+use tokio::io::AsyncReadExt;
 use tokio_util::compat::{TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
 
-use nvim_rs::rpc::{message::Message, RpcReader, RpcWriter};
+use nvim_rs::rpc::{message::Message, CallError, RpcReader, RpcWriter};
 use nvim_rs::{rpc::Caller, Client};
 
 #[tokio::test]
@@ -60,3 +62,123 @@ async fn void_response_decodes_correctly() {
         })
         .await;
 }
+
+#[tokio::test]
+async fn call_times_out() {
+    let (client, _server) = tokio::io::duplex(1024 * 64);
+    let (_reader, writer) = tokio::io::split(client);
+
+    let client = Client::new(writer.compat_write());
+
+    let res = (&client)
+        .call_with_timeout::<(), _, _, _>(
+            "never_answered",
+            (),
+            tokio::time::sleep(Duration::from_millis(10)),
+        )
+        .await;
+
+    assert!(matches!(res, Err(CallError::Timeout)));
+    assert_eq!(client.pending_calls(), 0);
+}
+
+#[tokio::test]
+async fn dropped_call_is_removed() {
+    let (client, _server) = tokio::io::duplex(1024 * 64);
+    let (_reader, writer) = tokio::io::split(client);
+
+    let client = Client::new(writer.compat_write());
+
+    let mut call = Box::pin((&client).call::<(), _, _>("never_answered", ()));
+    assert!(futures::poll!(&mut call).is_pending());
+    assert_eq!(client.pending_calls(), 1);
+
+    drop(call);
+    assert_eq!(client.pending_calls(), 0);
+}
+
+#[tokio::test]
+async fn cancelled_call_fails() {
+    let (client, _server) = tokio::io::duplex(1024 * 64);
+    let (_reader, writer) = tokio::io::split(client);
+
+    let client = Client::new(writer.compat_write());
+
+    let (msgid, call) = client.call_with_id::<(), _, _>("never_answered", ());
+    let mut call = Box::pin(call);
+    assert!(futures::poll!(&mut call).is_pending());
+    assert_eq!(client.pending_calls(), 1);
+
+    assert!(client.cancel(msgid));
+    assert!(matches!(call.await, Err(CallError::Cancelled)));
+    assert_eq!(client.pending_calls(), 0);
+
+    // Nothing to cancel anymore.
+    assert!(!client.cancel(msgid));
+}
+
+#[tokio::test]
+async fn recv_delivers_responses() {
+    let (client, server) = tokio::io::duplex(1024 * 64);
+    let (reader, writer) = tokio::io::split(client);
+    let mut reader: RpcReader<_> = reader.compat().into();
+
+    let client = Client::new(writer.compat_write());
+
+    let res = (&client).call::<i64, _, _>("get_one", ());
+    let serve = async {
+        let (reader, writer) = tokio::io::split(server);
+        let mut writer = writer.compat_write();
+        let mut reader: RpcReader<_> = reader.compat().into();
+
+        let req = match reader.recv().await.unwrap() {
+            Message::Request(req) => req,
+            msg => panic!("unexpected message: {:?}", msg),
+        };
+        (&mut writer)
+            .write_rpc_response(req.msgid, None::<&rmpv::Value>, Some(&rmpv::Value::from(1)))
+            .await
+            .unwrap();
+        writer
+            .write_rpc_notification("done", &Vec::<rmpv::Value>::new())
+            .await
+            .unwrap();
+    };
+    let read = async {
+        // The response is handled, and only the notification is returned.
+        match client.recv(&mut reader).await.unwrap() {
+            Message::Notification(notification) => assert_eq!(notification.method, "done"),
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+    };
+
+    let (res, _, _) = tokio::join!(res, serve, read);
+    assert_eq!(res.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn disconnect_fails_pending_calls() {
+    let (client, server) = tokio::io::duplex(1024 * 64);
+    let (reader, writer) = tokio::io::split(client);
+    let mut reader: RpcReader<_> = reader.compat().into();
+
+    let client = Client::new(writer.compat_write());
+
+    let res = (&client).call::<(), _, _>("never_answered", ());
+    let read = async {
+        // Close the "server" once it has received the request.
+        let mut server = server;
+        let mut buf = [0u8; 1];
+        server.read_exact(&mut buf).await.unwrap();
+        drop(server);
+
+        assert!(client.recv(&mut reader).await.is_err());
+    };
+
+    let (res, _) = tokio::join!(res, read);
+    assert!(matches!(res, Err(CallError::Disconnected)));
+
+    // Calls after the disconnect fail right away.
+    let res = (&client).call::<(), _, _>("nvim_get_mode", ()).await;
+    assert!(matches!(res, Err(CallError::Disconnected)));
+}
@@ -0,0 +1,144 @@
+use std::rc::Rc;
+
+use nvim_rs::rpc::{
+    message::Message, CallError, Caller, GridLineVisitor, LazyMessage, RedrawEvent, RpcReader,
+};
+use nvim_rs::testing::FakeNeovim;
+use nvim_rs::types::{ErrorType, Object, UiEvent, UiOptions};
+use nvim_rs::{Client, NeovimApi};
+
+fn v<T: Into<rmpv::Value>>(v: T) -> rmpv::Value {
+    v.into()
+}
+
+#[tokio::test]
+async fn fake_responds() {
+    let mut nvim = FakeNeovim::new();
+    nvim.respond("nvim_get_vvar", vec!["nvim", "--headless"])
+        .on_request("add", |(a, b): (i64, i64)| Ok(a + b));
+
+    let (server, reader, writer) = nvim.start();
+    let mut reader = RpcReader::new(reader);
+    let client = Rc::new(Client::new(writer));
+
+    let calls = async {
+        let argv = client.nvim_get_vvar("argv").await.unwrap();
+        assert_eq!(argv, Object::new(vec![v("nvim"), v("--headless")]));
+
+        let sum = (&*client).call::<i64, _, _>("add", (1, 2)).await.unwrap();
+        assert_eq!(sum, 3);
+
+        let res = (&*client).call::<(), _, _>("nvim_unknown", ()).await;
+        assert!(matches!(res, Err(CallError::Error(err)) if err.kind == ErrorType::Exception));
+
+        server.close();
+    };
+
+    let read = async {
+        loop {
+            match reader.recv().await {
+                Ok(Message::Response(res)) => client.handle_response(res).unwrap(),
+                Ok(msg) => panic!("unexpected message: {:?}", msg),
+                Err(_) => break,
+            }
+        }
+    };
+
+    let (res, _, _) = tokio::join!(server.run(), calls, read);
+    res.unwrap();
+}
+
+#[derive(Default)]
+struct Cells(String);
+
+impl GridLineVisitor for Cells {
+    fn cell(&mut self, text: &str, _hl_id: Option<i64>, repeat: Option<i64>) {
+        for _ in 0..repeat.unwrap_or(1) {
+            self.0.push_str(text);
+        }
+    }
+}
+
+#[tokio::test]
+async fn fake_redraws_after_attach() {
+    let mut nvim = FakeNeovim::new();
+    nvim.respond("nvim_ui_attach", ()).redraw_after(
+        "nvim_ui_attach",
+        vec![
+            v(vec![v("grid_resize"), v(vec![v(1), v(10), v(2)])]),
+            v(vec![
+                v("grid_line"),
+                v(vec![
+                    v(1),
+                    v(0),
+                    v(0),
+                    v(vec![v(vec![v("a"), v(1)]), v(vec![v(" "), v(1), v(9)])]),
+                    v(false),
+                ]),
+            ]),
+            v(vec![v("flush"), v(Vec::<rmpv::Value>::new())]),
+        ],
+    );
+
+    let (server, reader, writer) = nvim.start();
+    let server = Rc::new(server);
+    let mut reader = RpcReader::new(reader);
+    let client = Rc::new(Client::new(writer));
+
+    let c = client.clone();
+    let attach = c.nvim_ui_attach(10, 2, UiOptions::default());
+
+    let s = server.clone();
+    let read = async move {
+        let mut cells = Cells::default();
+        let mut events = Vec::new();
+
+        loop {
+            match reader.recv_lazy().await.unwrap() {
+                LazyMessage::Message(Message::Response(res)) => {
+                    client.handle_response(res).unwrap()
+                }
+                LazyMessage::Redraw(redraw) => {
+                    for event in redraw.events() {
+                        match event.unwrap() {
+                            RedrawEvent::GridLine(line) => line.visit_cells(&mut cells).unwrap(),
+                            RedrawEvent::Event(event) => events.push(event),
+                        }
+                    }
+
+                    if matches!(events.last(), Some(UiEvent::Flush)) {
+                        break;
+                    }
+                }
+                msg => panic!("unexpected message: {:?}", msg),
+            }
+        }
+
+        s.close();
+        (cells, events)
+    };
+
+    let (res, attach, (cells, events)) = tokio::join!(server.run(), attach, read);
+    res.unwrap();
+    attach.unwrap();
+
+    assert_eq!(cells.0, format!("a{}", " ".repeat(9)));
+    assert!(matches!(events[0], UiEvent::GridResize(_)));
+    assert_eq!(server.received()[0].0, "nvim_ui_attach");
+}
+
+#[tokio::test]
+async fn fake_close_disconnects() {
+    let (server, reader, writer) = FakeNeovim::new().start();
+    let mut reader = RpcReader::new(reader);
+    let client = Client::new(writer);
+
+    let call = (&client).call::<(), _, _>("nvim_command", ("qa!",));
+    let read = async {
+        server.close();
+        assert!(client.recv(&mut reader).await.is_err());
+    };
+
+    let (res, _) = tokio::join!(call, read);
+    assert!(matches!(res, Err(CallError::Disconnected)));
+}
@@ -117,3 +117,26 @@ async fn smoke_test_ui_attach() {
     let (res, _) = tokio::join!(res, read);
     assert!(res.is_ok());
 }
+
+#[test]
+fn decode_unknown_ui_event() {
+    let params = rmpv::Value::from(vec![rmpv::Value::from(vec![
+        rmpv::Value::from("some_future_event"),
+        rmpv::Value::from(vec![rmpv::Value::from(1), rmpv::Value::from("foo")]),
+    ])]);
+
+    let events = decode_redraw_params(params).unwrap();
+    match events.as_slice() {
+        [UiEvent::Unknown { name, args }] => {
+            assert_eq!(name, "some_future_event");
+            assert_eq!(
+                args,
+                &vec![rmpv::Value::from(vec![
+                    rmpv::Value::from(1),
+                    rmpv::Value::from("foo")
+                ])]
+            );
+        }
+        events => panic!("unexpected events: {:?}", events),
+    }
+}
@@ -1,4 +1,4 @@
-local M = { popupmenu = {} }
+local M = { popupmenu = {}, clipboard = {} }
 
 function M.find_gnvim_chans()
   local nvim_chans = {}
@@ -43,6 +43,20 @@ end
 
 function M.setup(opts)
   M.notify('setup', opts)
+
+  -- Gnvim needs to know the filetype for the filetype based icons.
+  local group = vim.api.nvim_create_augroup('gnvim_icon', { clear = true })
+  if vim.tbl_get(opts or {}, 'icon', 'filetypes') then
+    local notify = function()
+      M.notify('filetype', { filetype = vim.bo.filetype })
+    end
+
+    vim.api.nvim_create_autocmd({ 'BufEnter', 'FileType' }, {
+      group = group,
+      callback = notify,
+    })
+    notify()
+  end
 end
 
 --- Adjust the font size.
@@ -53,6 +67,120 @@ function M.font_size(increment)
   M.notify('font_size', { increment = increment })
 end
 
+--- Detach the GUI on channel `chan`, leaving neovim running. Without `chan`,
+--- detaches all the gnvim GUIs.
+---
+---@param chan Channel id of the GUI to detach (optional)
+function M.detach(chan)
+  if chan == nil then
+    M.notify('detach')
+  else
+    vim.rpcnotify(chan, 'gnvim', { ['fn'] = 'detach' })
+  end
+end
+
+--- Use gnvim as the clipboard provider (see `:h clipboard`), unless
+--- `g:clipboard` is already set. Gnvim calls this once it has attached.
+---
+---@param chan Channel of the gnvim GUI to prefer (optional)
+function M.clipboard.setup(chan)
+  M.clipboard.focus(chan)
+
+  if vim.g.clipboard ~= nil then
+    return
+  end
+
+  local request = function(...)
+    local chan = M.clipboard.chan()
+    if chan == nil then
+      error('gnvim: no gnvim ui attached')
+    end
+
+    return vim.rpcrequest(chan, ...)
+  end
+
+  local copy = function(selection)
+    return function(lines, regtype)
+      request('clipboard_set', selection, lines, regtype)
+    end
+  end
+
+  local paste = function(selection)
+    return function()
+      return request('clipboard_get', selection)
+    end
+  end
+
+  vim.g.clipboard = {
+    name = 'gnvim',
+    copy = { ['+'] = copy('clipboard'), ['*'] = copy('primary') },
+    paste = { ['+'] = paste('clipboard'), ['*'] = paste('primary') },
+  }
+
+  -- The provider might've been loaded already (without us).
+  vim.g.loaded_clipboard_provider = nil
+  vim.cmd.runtime('autoload/provider/clipboard.vim')
+
+  -- Go back to the stock provider once the last gnvim ui is gone.
+  local group = vim.api.nvim_create_augroup('gnvim_clipboard', { clear = true })
+  vim.api.nvim_create_autocmd('UILeave', {
+    group = group,
+    callback = function()
+      local chans = M.find_gnvim_chans()
+      for _, ui in ipairs(vim.api.nvim_list_uis()) do
+        if ui.chan ~= vim.v.event.chan and vim.tbl_contains(chans, ui.chan) then
+          return
+        end
+      end
+
+      M.clipboard.teardown()
+    end,
+  })
+end
+
+--- Prefer the gnvim GUI on channel `chan` for the clipboard. Gnvim calls this
+--- when its window is focused.
+---
+---@param chan Channel of the gnvim GUI
+function M.clipboard.focus(chan)
+  M.clipboard.focused = chan
+end
+
+--- Channel of the gnvim GUI that the clipboard provider uses: the most recently
+--- focused one, if it's still attached, otherwise the most recently attached
+--- one.
+function M.clipboard.chan()
+  local chans = M.find_gnvim_chans()
+  local attached = {}
+  -- In the order the GUIs attached.
+  for _, ui in ipairs(vim.api.nvim_list_uis()) do
+    if vim.tbl_contains(chans, ui.chan) then
+      table.insert(attached, ui.chan)
+    end
+  end
+
+  if vim.tbl_contains(attached, M.clipboard.focused) then
+    return M.clipboard.focused
+  end
+
+  return attached[#attached]
+end
+
+--- Removes the provider installed by `M.clipboard.setup()`, falling back to
+--- the stock one.
+function M.clipboard.teardown()
+  pcall(vim.api.nvim_del_augroup_by_name, 'gnvim_clipboard')
+
+  -- Leave other providers alone, and skip reloading when exiting.
+  if vim.tbl_get(vim.g, 'clipboard', 'name') ~= 'gnvim' or vim.v.exiting ~= vim.NIL then
+    return
+  end
+
+  vim.g.clipboard = nil
+  vim.g.loaded_clipboard_provider = nil
+  vim.cmd.runtime('autoload/provider/clipboard.vim')
+end
+
 function M.popupmenu.kind(label, hl)
   local adapt = function(hl, normal)
     local attrs = vim.api.nvim_get_hl(0, { name = hl })
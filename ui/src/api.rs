@@ -15,8 +15,16 @@ pub enum GnvimEvent {
     EchoRepeat(EchoRepeat),
     GtkDebugger,
     Setup(Setup),
+    Filetype(Filetype),
 
     FontSize(FontSize),
+    Detach,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(crate = "nvim::serde")]
+pub struct Filetype {
+    pub filetype: String,
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -41,6 +49,56 @@ pub struct Setup {
     pub scroll_transition: ScrollTransition,
     #[serde(default)]
     pub popupmenu: Popupmenu,
+    #[serde(default)]
+    pub bell: Bell,
+    #[serde(default)]
+    pub icon: Icon,
+    #[serde(default)]
+    pub suspend: Suspend,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(crate = "nvim::serde")]
+pub struct Suspend {
+    /// Shell command to run on suspend, instead of minimizing the window.
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(crate = "nvim::serde")]
+pub struct Icon {
+    /// Filetype to themed icon name.
+    #[serde(default)]
+    pub filetypes: HashMap<String, String>,
+    /// 'iconstring' to themed icon name.
+    #[serde(default)]
+    pub iconstrings: HashMap<String, String>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+#[serde(crate = "nvim::serde", default)]
+pub struct Bell {
+    /// Beep on the (audible) bell.
+    pub audible: bool,
+    /// Flash the active grid on the (audible) bell. The visual bell always
+    /// flashes.
+    pub visual: bool,
+    /// Set the urgency hint when the window isn't focused.
+    pub urgent: bool,
+    /// Duration of the flash.
+    pub duration: BellDuration,
+}
+
+impl Default for Bell {
+    fn default() -> Self {
+        Self {
+            audible: true,
+            visual: false,
+            urgent: true,
+            duration: BellDuration::default(),
+        }
+    }
 }
 
 #[derive(Debug, Default, serde::Deserialize)]
@@ -106,3 +164,4 @@ macro_rules! defaulted_f64 {
 defaulted_f64!(CursorBlinkTransition, 160.0);
 defaulted_f64!(CursorPositionTransition, 150.0);
 defaulted_f64!(ScrollTransition, 300.0);
+defaulted_f64!(BellDuration, 100.0);
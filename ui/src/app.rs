@@ -19,6 +19,16 @@ impl std::ops::Deref for Fd {
     }
 }
 
+/// Exit status for `--wait`, from the results of `AppWindow::edit_and_wait`:
+/// the first non-zero status, treating failed waits as errors.
+fn wait_status(statuses: impl IntoIterator<Item = Option<i32>>) -> i32 {
+    statuses
+        .into_iter()
+        .map(|status| status.unwrap_or(1))
+        .find(|status| *status != 0)
+        .unwrap_or(0)
+}
+
 impl Default for App {
     fn default() -> Self {
         let mut flags = gio::ApplicationFlags::empty();
@@ -34,12 +44,20 @@ impl Default for App {
 mod imp {
     #[cfg(feature = "flatpak")]
     use std::process::Command;
-    use std::{cell::RefCell, io::IsTerminal};
+    use std::{
+        cell::{Cell, RefCell},
+        io::IsTerminal,
+        path::PathBuf,
+    };
 
     use adw::subclass::prelude::*;
+    use futures::future;
     use gtk::{gio, prelude::*};
 
-    use crate::{components::appwindow::AppWindow, debug};
+    use crate::{
+        components::{appwindow::AppWindow, picker, SessionPicker},
+        debug, spawn_local, warn,
+    };
 
     #[cfg(not(feature = "flatpak"))]
     fn default_rtp() -> Option<String> {
@@ -73,6 +91,29 @@ mod imp {
 
         #[property(get, set, nullable)]
         connect_addr: RefCell<Option<String>>,
+        #[property(get, set, nullable)]
+        connect_cmd: RefCell<Option<String>>,
+
+        #[property(get, set, nullable)]
+        record: RefCell<Option<String>>,
+        #[property(get, set, nullable)]
+        replay: RefCell<Option<String>>,
+        #[property(get, set)]
+        replay_fast: Cell<bool>,
+
+        #[property(get, set)]
+        pick: Cell<bool>,
+        picker: RefCell<Option<SessionPicker>>,
+        /// Files to open once a server has been picked.
+        pending_files: RefCell<Vec<gio::File>>,
+
+        /// File arguments as given, when they're relative to neovim's working
+        /// directory instead of ours (see `handle_local_options`).
+        args: RefCell<Vec<PathBuf>>,
+
+        /// Exit status of `--wait`, when we're the primary instance.
+        #[property(get)]
+        wait_status: Cell<i32>,
     }
 
     impl App {
@@ -103,7 +144,11 @@ mod imp {
                         .property("application", obj.upcast_ref::<adw::Application>())
                         .property("nvim-args", args)
                         .property("connect-addr", self.connect_addr.borrow_mut().take())
+                        .property("connect-cmd", self.connect_cmd.borrow_mut().take())
                         .property("stdin-fd", *self.stdin_fd.borrow())
+                        .property("record-path", self.record.borrow_mut().take())
+                        .property("replay-path", self.replay.borrow_mut().take())
+                        .property("replay-fast", obj.replay_fast())
                         .build();
 
                     // Handle `:restart` by looping back to this same function
@@ -129,6 +174,77 @@ mod imp {
                 })
                 .clone()
         }
+
+        /// Passes piped stdin to the primary instance, if there is one.
+        /// Otherwise neovim reads it itself, see `:h ui-startup-stdin`.
+        fn handle_stdin(&self) {
+            let obj = self.obj();
+            let unique = !obj.flags().contains(gio::ApplicationFlags::NON_UNIQUE);
+            if unique {
+                if let Err(err) = obj.register(gio::Cancellable::NONE) {
+                    warn!("failed to register: {}", err);
+                }
+            }
+
+            if unique && obj.is_remote() {
+                if let Err(err) = crate::fd::send_stdin(obj.upcast_ref()) {
+                    warn!("failed to pass stdin to the running instance: {}", err);
+                }
+                return;
+            }
+
+            // Duplicate the fd for the nvim subprocess.
+            *self.stdin_fd.borrow_mut() = super::Fd(crate::fd::dup_stdin());
+        }
+
+        /// Shows the session picker, and opens the main window (with `files`)
+        /// once something is picked.
+        fn show_picker(&self, files: &[gio::File]) {
+            self.pending_files.borrow_mut().extend_from_slice(files);
+
+            if let Some(ref picker) = *self.picker.borrow() {
+                picker.present();
+                return;
+            }
+
+            let obj = self.obj();
+            let picker = SessionPicker::new(&*obj);
+            picker.connect_closure(
+                "picked",
+                false,
+                glib::closure_local!(
+                    #[weak]
+                    obj,
+                    move |picker: SessionPicker, addr: Option<String>| {
+                        let imp = obj.imp();
+                        obj.set_pick(false);
+                        obj.set_connect_addr(addr);
+
+                        let win = imp.main_window();
+                        imp.open_files(&win, &imp.pending_files.take());
+                        win.present();
+
+                        imp.picker.borrow_mut().take();
+                        picker.close();
+                    }
+                ),
+            );
+
+            picker.scan();
+            picker.present();
+            self.picker.replace(Some(picker));
+        }
+
+        /// Opens `files` in `win`, or our own file arguments if we kept them
+        /// as is (see `handle_local_options`).
+        fn open_files(&self, win: &AppWindow, files: &[gio::File]) {
+            let args = self.args.take();
+            if args.is_empty() {
+                win.open_files(files);
+            } else {
+                win.open_paths(&args);
+            }
+        }
     }
 
     #[glib::object_subclass]
@@ -199,6 +315,59 @@ mod imp {
                 "Connect to neovim instance",
                 None,
             );
+            obj.add_main_option(
+                "connect-cmd",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::String,
+                "Run neovim through a shell command, i.e. --connect-cmd \"ssh host nvim --embed\"",
+                None,
+            );
+
+            obj.add_main_option(
+                "wait",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::None,
+                "Wait for the files to be closed before returning, e.g. for $EDITOR. \
+                Exits with non-zero status on :cq",
+                None,
+            );
+
+            obj.add_main_option(
+                "pick",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::None,
+                "Pick a running neovim server to connect to, or start a new one. \
+                Also shown on start-up when no servers are running",
+                None,
+            );
+
+            obj.add_main_option(
+                "record",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::String,
+                "Record the messages between gnvim and neovim to a file",
+                None,
+            );
+            obj.add_main_option(
+                "replay",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::String,
+                "Replay a session recorded with --record, without neovim",
+                None,
+            );
+            obj.add_main_option(
+                "replay-fast",
+                glib::Char::from(0),
+                glib::OptionFlags::NONE,
+                glib::OptionArg::None,
+                "Replay as fast as possible, instead of at the recorded speed",
+                None,
+            );
 
             #[cfg(feature = "flatpak")]
             obj.add_main_option(
@@ -238,25 +407,32 @@ mod imp {
         fn startup(&self) {
             debug!("Application::startup");
             self.parent_startup();
+
+            let obj = self.obj();
+            if obj.dbus_connection().is_some() {
+                let res = crate::fd::register_stdin(
+                    obj.upcast_ref(),
+                    glib::clone!(
+                        #[weak]
+                        obj,
+                        move |stream| {
+                            let win = obj.imp().main_window();
+                            win.open_stream(stream);
+                            win.present();
+                        }
+                    ),
+                );
+
+                if let Err(err) = res {
+                    warn!("failed to accept stdin from other instances: {}", err);
+                }
+            }
         }
 
         fn handle_local_options(&self, options: &glib::VariantDict) -> glib::ExitCode {
             debug!("Application::handle_local_options");
             let obj = self.obj();
 
-            // Duplicate the stdin fd if the user is trying to pipe content to us.
-            // See `:h ui-startup-stdin`.
-            if !std::io::stdin().is_terminal() && !options.contains("no-stdin") {
-                // Duplicate the fd for the nvim subprocess.
-                *self.stdin_fd.borrow_mut() = super::Fd(crate::fd::dup_stdin());
-
-                // We don't currently support opening stdin over dbus, so don't
-                // try to reuse any existing instances.
-                let mut flags = obj.flags();
-                flags.insert(gio::ApplicationFlags::NON_UNIQUE);
-                obj.set_flags(flags);
-            }
-
             if options.contains("version") {
                 println!("gnvim {}", env!("CARGO_PKG_VERSION"));
                 return glib::ExitCode::from(0);
@@ -341,19 +517,150 @@ mod imp {
                 obj.set_connect_addr(Some(connect_addr));
             }
 
+            if let Some(connect_cmd) = options
+                .lookup::<String>("connect-cmd")
+                .expect("invalid connect-cmd argument type")
+            {
+                debug!("connect-cmd arg: {}", connect_cmd);
+                obj.set_connect_cmd(Some(connect_cmd));
+            }
+
+            obj.set_pick(options.contains("pick"));
+
+            // When connecting to a server, relative paths are relative to
+            // its working directory. Keep the file arguments as they are,
+            // instead of the files gio resolves against ours.
+            if obj.connect_addr().is_some() || obj.connect_cmd().is_some() || obj.pick() {
+                // G_OPTION_REMAINING.
+                if let Ok(Some(args)) = options.lookup::<Vec<PathBuf>>("") {
+                    self.args.replace(args);
+                }
+            }
+
+            if let Some(record) = options
+                .lookup::<String>("record")
+                .expect("invalid record argument type")
+            {
+                debug!("record arg: {}", record);
+                obj.set_record(Some(record));
+            }
+
+            if let Some(replay) = options
+                .lookup::<String>("replay")
+                .expect("invalid replay argument type")
+            {
+                debug!("replay arg: {}", replay);
+                obj.set_replay(Some(replay));
+                obj.set_replay_fast(options.contains("replay-fast"));
+            }
+
+            // Pass the files over to `command_line`, which can keep the
+            // caller waiting (even when it's a remote instance).
+            if options.contains("wait") {
+                let mut flags = obj.flags();
+                flags.insert(gio::ApplicationFlags::HANDLES_COMMAND_LINE);
+                obj.set_flags(flags);
+            }
+
+            // Running a command, picking a server, recording and replaying
+            // only make sense for a new instance.
+            if obj.connect_cmd().is_some()
+                || obj.pick()
+                || obj.record().is_some()
+                || obj.replay().is_some()
+            {
+                let mut flags = obj.flags();
+                flags.insert(gio::ApplicationFlags::NON_UNIQUE);
+                obj.set_flags(flags);
+            }
+
+            // Check if the user is trying to pipe content to us. Done last, so
+            // that we know if we're going to be a new instance.
+            if !std::io::stdin().is_terminal() && !options.contains("no-stdin") {
+                self.handle_stdin();
+            }
+
             self.parent_handle_local_options(options)
         }
 
         fn open(&self, files: &[gtk::gio::File], _hint: &str) {
+            if self.obj().pick() {
+                self.show_picker(files);
+                return;
+            }
+
             let win = self.main_window();
-            win.open_files(files);
+            self.open_files(&win, files);
             win.present();
         }
 
+        fn command_line(&self, cmdline: &gio::ApplicationCommandLine) -> glib::ExitCode {
+            debug!("Application::command_line");
+
+            let files = cmdline
+                .arguments()
+                .iter()
+                .skip(1)
+                .map(|arg| cmdline.create_file_for_arg(arg))
+                .collect::<Vec<_>>();
+
+            let win = self.main_window();
+            win.present();
+
+            // The caller is released once `cmdline` is dropped.
+            spawn_local!(glib::clone!(
+                #[strong]
+                cmdline,
+                #[weak(rename_to = obj)]
+                self.obj(),
+                async move {
+                    let statuses =
+                        future::join_all(files.iter().map(|file| win.edit_and_wait(file))).await;
+                    let status = super::wait_status(statuses);
+
+                    debug!("--wait done with status {}", status);
+                    cmdline.set_exit_status(status);
+                    if !cmdline.is_remote() {
+                        obj.imp().wait_status.set(status);
+                    }
+                }
+            ));
+
+            glib::ExitCode::SUCCESS
+        }
+
         fn activate(&self) {
+            let obj = self.obj();
+            // When starting up without being told what to run, and no servers
+            // are running, let the user pick what to do.
+            let plain_startup = self.window.borrow().is_none()
+                && obj.connect_addr().is_none()
+                && obj.connect_cmd().is_none()
+                && obj.replay().is_none()
+                && self.stdin_fd.borrow().is_none();
+            if obj.pick() || (plain_startup && picker::server_sockets().is_empty()) {
+                self.show_picker(&[]);
+                return;
+            }
+
             self.main_window().present();
         }
     }
 
     impl GtkApplicationImpl for App {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::wait_status;
+
+    #[test]
+    fn test_wait_status() {
+        assert_eq!(wait_status([]), 0);
+        assert_eq!(wait_status([Some(0), Some(0)]), 0);
+        // Exiting with :cq.
+        assert_eq!(wait_status([Some(0), Some(1)]), 1);
+        // The window was closed first.
+        assert_eq!(wait_status([None, Some(0)]), 1);
+    }
+}
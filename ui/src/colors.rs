@@ -6,6 +6,8 @@ use crate::api;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum HlGroup {
+    ErrorMsg,
+    MsgArea,
     MsgSeparator,
     Pmenu,
     PmenuSel,
@@ -14,6 +16,7 @@ pub enum HlGroup {
     TabLine,
     TabLineFill,
     TabLineSel,
+    WarningMsg,
 }
 
 #[derive(Clone, Debug, Default)]
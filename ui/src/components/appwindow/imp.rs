@@ -1,17 +1,26 @@
 use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
+use std::fs::File;
+use std::io::BufReader;
+use std::rc::Rc;
 use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 
 use nvim::dict;
 use nvim::rpc::message::{Message, Request};
-use nvim::rpc::ReadError;
+use nvim::rpc::record::{self, Direction};
+use nvim::rpc::{
+    CallError, Caller, Dispatcher, LazyMessage, NvimError, ReadError, Redraw, RedrawEvent,
+};
 use nvim::serde::Deserialize;
 use nvim::types::uievents::{DefaultColorsSet, HlGroupSet, PopupmenuSelect, PopupmenuShow};
 use nvim::types::UiEvent;
-use nvim::types::{OptionSet, UiOptions};
+use nvim::types::{ErrorType, OptionSet, UiOptions};
 use nvim::NeovimApi;
 
 use adw::{self, prelude::*, subclass::prelude::*};
+use futures::channel::oneshot;
 use glib::subclass::{InitializingObject, Signal};
 use gtk::gio;
 use gtk::CompositeTemplate;
@@ -26,12 +35,59 @@ use crate::api::{self, GnvimEvent};
 use crate::app::Fd;
 use crate::boxed::{ModeInfo, ShowTabline};
 use crate::colors::{Color, Colors, HlGroup};
-use crate::components::{popupmenu, Cmdline, Shell, Tabline};
+use crate::components::{popupmenu, Cmdline, Messages, MsgStrip, Shell, Tabline};
 use crate::font::Font;
+use crate::menu;
 use crate::nvim::Neovim;
-use crate::{debug, warn, APPID};
+use crate::{debug, some_or_return, warn, APPID};
 use crate::{spawn_local, SCALE};
 
+/// Delays between the attempts to reconnect.
+const RECONNECT_DELAY_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(30);
+
+/// Makes a `wait_done` request with the given id once the current buffer is
+/// closed (zero status), or neovim exits (`v:exiting`). Arguments: our
+/// channel and the id.
+///
+/// BufUnload would fire on reloads (e.g. `:e!`) too, so the buffer has to be
+/// deleted or wiped out instead.
+const WAIT_LUA: &str = r#"
+local chan, id = ...
+local group = vim.api.nvim_create_augroup('gnvim_wait_' .. id, {})
+local function done(status)
+  vim.api.nvim_del_augroup_by_id(group)
+  vim.rpcrequest(chan, 'wait_done', id, status)
+end
+vim.api.nvim_create_autocmd({ 'BufDelete', 'BufWipeout' }, {
+  group = group,
+  buffer = vim.api.nvim_get_current_buf(),
+  callback = function() done(0) end,
+})
+vim.api.nvim_create_autocmd('VimLeavePre', {
+  group = group,
+  callback = function() done(vim.v.exiting) end,
+})
+"#;
+
+fn ui_options() -> UiOptions {
+    UiOptions {
+        rgb: true,
+        ext_linegrid: true,
+        ext_multigrid: true,
+        ext_popupmenu: true,
+        ext_tabline: true,
+        ext_cmdline: true,
+        ext_messages: true,
+        ..Default::default()
+    }
+}
+
+/// Name of the augroup for our VimLeavePre autocmd on channel `chan_id`.
+fn vimleavepre_augroup(chan_id: i64) -> String {
+    format!("gnvim_vimleavepre_{}", chan_id)
+}
+
 #[derive(Default)]
 struct CursorOpts {
     blink_transition: api::CursorBlinkTransition,
@@ -68,6 +124,14 @@ pub struct AppWindow {
     tabline: TemplateChild<Tabline>,
     #[template_child(id = "cmdline")]
     cmdline: TemplateChild<Cmdline>,
+    #[template_child(id = "messages")]
+    messages: TemplateChild<Messages>,
+    #[template_child(id = "msgstrip")]
+    msgstrip: TemplateChild<MsgStrip>,
+    #[template_child(id = "menubar")]
+    menubar: TemplateChild<gtk::PopoverMenuBar>,
+    #[template_child(id = "banner")]
+    banner: TemplateChild<adw::Banner>,
 
     settings: Settings,
 
@@ -93,9 +157,33 @@ pub struct AppWindow {
 
     popupmenu_kinds: RefCell<popupmenu::Kinds>,
 
+    bell: RefCell<api::Bell>,
+    /// If we've set the urgency hint, and need to clear it once focused.
+    urgent: Cell<bool>,
+
+    suspend: RefCell<api::Suspend>,
+    icon: RefCell<api::Icon>,
+    /// Latest 'iconstring' from neovim.
+    iconstring: RefCell<String>,
+    /// Filetype of the current buffer, if we're tracking it.
+    filetype: RefCell<String>,
+
     #[property(get)]
     nvim: Neovim,
     nvim_exited: Cell<bool>,
+    /// Our channel id in neovim.
+    chan_id: Cell<Option<i64>>,
+    /// Set when we've lost the connection, and are trying to reconnect.
+    disconnected: Cell<bool>,
+    /// Set when we've detached from neovim, and are closing.
+    detached: Cell<bool>,
+    /// Register types of what we've put to the clipboards, by selection.
+    clipboard_regtypes: RefCell<HashMap<String, String>>,
+    /// Buffers we're waiting to be closed (see `edit_and_wait`).
+    waits: RefCell<HashMap<u64, oneshot::Sender<i32>>>,
+    next_wait_id: Cell<u64>,
+    /// Handler for the requests from neovim.
+    handler: RefCell<Rc<Dispatcher<'static>>>,
     #[property(get, set, construct_only)]
     nvim_args: RefCell<Vec<String>>,
     #[property(get, set, construct_only)]
@@ -103,6 +191,17 @@ pub struct AppWindow {
 
     #[property(set, nullable, construct_only)]
     connect_addr: RefCell<Option<String>>,
+    /// Shell command to run neovim through.
+    #[property(set, nullable, construct_only)]
+    connect_cmd: RefCell<Option<String>>,
+    /// File to record the session to.
+    #[property(set, nullable, construct_only)]
+    record_path: RefCell<Option<String>>,
+    /// Recorded session to replay, instead of running neovim.
+    #[property(set, nullable, construct_only)]
+    replay_path: RefCell<Option<String>>,
+    #[property(set, construct_only)]
+    replay_fast: Cell<bool>,
     #[property(get, set)]
     pending_restart_addr: RefCell<Option<String>>,
 
@@ -113,6 +212,9 @@ pub struct AppWindow {
     mode_infos: RefCell<Vec<ModeInfo>>,
     #[property(get, set)]
     show_tabline: RefCell<ShowTabline>,
+    /// Neovim's current working directory.
+    #[property(get, set)]
+    cwd: RefCell<String>,
 
     /// When resize on flush is set, there were some operations on the previous
     /// ui events that changed our grid size (e.g. font chagned etc.).
@@ -120,6 +222,8 @@ pub struct AppWindow {
     /// Set when attributes affecting our CSS changed, and we need to regenerate
     /// the css.
     css_on_flush: Cell<bool>,
+    /// Events we've already warned about not handling.
+    unhandled_events: RefCell<HashSet<String>>,
 }
 
 impl AppWindow {
@@ -132,19 +236,15 @@ impl AppWindow {
             }
             Message::Request(req) => self.handle_request(req),
             Message::Notification(Notification { method, params, .. }) => match method.as_ref() {
-                "redraw" => match params {
-                    rmpv::Value::Array(params) => params
-                        .into_iter()
-                        .map(rmpv::ext::from_value::<UiEvent>)
-                        .for_each(|event| match event {
-                            Ok(event) => self.handle_ui_event(event),
-                            Err(err) => self.handle_decode_redraw_error(err),
-                        }),
-                    params => self.handle_decode_redraw_error(format_args!(
-                        "Invalid params type: {:?}",
-                        params
-                    )),
-                },
+                "redraw" => {
+                    // Redraws are normally received as `LazyMessage::Redraw`
+                    // already, but if not, go through the same path anyway.
+                    let mut buf = Vec::new();
+                    match rmpv::encode::write_value(&mut buf, &params) {
+                        Ok(()) => self.handle_redraw(Redraw::new(buf)),
+                        Err(err) => self.handle_decode_redraw_error(err),
+                    }
+                }
                 "gnvim" => match params {
                     rmpv::Value::Array(params) => params
                         .into_iter()
@@ -162,36 +262,85 @@ impl AppWindow {
         }
     }
 
-    fn handle_request(&self, req: Request<'_, rmpv::Value>) {
-        match req.method.as_ref() {
-            "vimleavepre" => {
-                self.nvim_exited.set(true);
+    fn handle_redraw(&self, redraw: Redraw) {
+        for event in redraw.events() {
+            let res = match event {
+                Ok(RedrawEvent::GridLine(event)) => self.shell.handle_grid_line(&event),
+                Ok(RedrawEvent::Event(event)) => {
+                    self.handle_ui_event(event);
+                    Ok(())
+                }
+                Err(err) => Err(err),
+            };
 
-                spawn_local!(glib::clone!(
-                    #[strong(rename_to = nvim)]
-                    self.nvim,
-                    async move {
-                        nvim.write_empty_rpc_response(req.msgid)
-                            .await
-                            .expect("write_rpc_response failed");
-                    }
-                ));
-            }
-            _ => {
-                warn!("unexpected request from nvim: {}", req.method);
-                spawn_local!(glib::clone!(
-                    #[strong(rename_to = nvim)]
-                    self.nvim,
-                    async move {
-                        nvim.write_rpc_response(req.msgid, Some(&"unexpected request"), None::<&()>)
-                            .await
-                            .expect("write_rpc_response failed")
-                    }
-                ));
+            if let Err(err) = res {
+                self.handle_decode_redraw_error(err);
             }
         }
     }
 
+    fn handle_request(&self, req: Request<'static, rmpv::Value>) {
+        let handler = self.handler.borrow().clone();
+        spawn_local!(glib::clone!(
+            #[strong(rename_to = nvim)]
+            self.nvim,
+            async move {
+                nvim.handle_request(handler.as_ref(), req)
+                    .await
+                    .expect("write_rpc_response failed");
+            }
+        ));
+    }
+
+    fn create_handler(&self) -> Dispatcher<'static> {
+        let mut handler = Dispatcher::new();
+
+        let obj = self.obj().downgrade();
+        handler.request("vimleavepre", move |_: Vec<rmpv::Value>| {
+            if let Some(obj) = obj.upgrade() {
+                obj.imp().nvim_exited.set(true);
+            }
+
+            async { Ok::<_, NvimError>(()) }
+        });
+
+        let obj = self.obj().downgrade();
+        handler.request(
+            "clipboard_set",
+            move |(selection, lines, regtype): (String, Vec<String>, String)| {
+                let res = match obj.upgrade() {
+                    Some(obj) => obj.imp().clipboard_set(&selection, lines, regtype),
+                    None => Ok(()),
+                };
+
+                async { res }
+            },
+        );
+
+        let obj = self.obj().downgrade();
+        handler.request("clipboard_get", move |(selection,): (String,)| {
+            let obj = obj.upgrade();
+            async move {
+                let obj = obj.ok_or_else(|| NvimError::new(ErrorType::Exception, "no window"))?;
+                obj.imp().clipboard_get(&selection).await
+            }
+        });
+
+        let obj = self.obj().downgrade();
+        handler.request("wait_done", move |(id, exiting): (u64, Option<i32>)| {
+            if let Some(tx) = obj
+                .upgrade()
+                .and_then(|obj| obj.imp().waits.borrow_mut().remove(&id))
+            {
+                let _ = tx.send(exiting.unwrap_or(0));
+            }
+
+            async { Ok::<_, NvimError>(()) }
+        });
+
+        handler
+    }
+
     fn show_error_dialog(&self, heading: &str, body_markup: &str) {
         const RESPONSE_ID: &str = "CLOSE";
         let dialog = adw::AlertDialog::builder()
@@ -232,23 +381,38 @@ impl AppWindow {
         );
     }
 
-    fn handle_io_error<D: std::fmt::Display>(&self, err: D) {
-        self.show_error_dialog(
-            "Fatal IO error",
-            &format!(
-                "Communication with Neovim failed with the following error:\n\n\
-                <tt>{}</tt>",
-                glib::markup_escape_text(&format!("{}", err))
-            ),
+    fn handle_io_error<D: std::fmt::Display>(&self, err: D, stderr: Option<&str>) {
+        let mut body = format!(
+            "Communication with Neovim failed with the following error:\n\n\
+            <tt>{}</tt>",
+            glib::markup_escape_text(&format!("{}", err))
         );
+
+        if let Some(stderr) = stderr.map(str::trim).filter(|stderr| !stderr.is_empty()) {
+            body.push_str(&format!(
+                "\n\nOutput (stderr):\n\n<tt>{}</tt>",
+                glib::markup_escape_text(stderr)
+            ));
+        }
+
+        self.show_error_dialog("Fatal IO error", &body);
     }
 
     async fn io_loop<R: futures::AsyncRead + Unpin>(&self, reader: R) {
         let mut reader: RpcReader<R> = reader.into();
 
+        if self.nvim.is_recording() {
+            reader.set_tee(glib::clone!(
+                #[weak(rename_to = nvim)]
+                self.nvim,
+                move |data: &[u8]| nvim.record(Direction::Incoming, data)
+            ));
+        }
+
         loop {
-            match reader.recv().await {
-                Ok(msg) => self.process_nvim_event(msg),
+            match reader.recv_lazy().await {
+                Ok(LazyMessage::Redraw(redraw)) => self.handle_redraw(redraw),
+                Ok(LazyMessage::Message(msg)) => self.process_nvim_event(msg),
                 Err(ReadError::IOError(_)) if self.nvim_exited.get() => {
                     debug!("clean exit");
                     if let Some(ref addr) = *self.pending_restart_addr.borrow() {
@@ -258,17 +422,482 @@ impl AppWindow {
                     }
                     break;
                 }
+                Err(_) if self.detached.get() => {
+                    debug!("detached");
+                    break;
+                }
+                Err(err) if self.connect_addr.borrow().is_some() => {
+                    warn!("io error: {:?}", err);
+                    spawn_local!(glib::clone!(
+                        #[strong(rename_to = app)]
+                        self.obj(),
+                        async move {
+                            app.imp().reconnect().await;
+                        }
+                    ));
+                    break;
+                }
                 Err(err) => {
                     warn!("io error: {:?}", err);
-                    self.handle_io_error(err);
+                    let stderr = self.nvim.take_stderr().await;
+                    self.handle_io_error(err, stderr.as_deref());
                     break;
                 }
             };
         }
     }
 
+    /// Starts (or connects to) neovim, and attaches the ui.
+    fn start_nvim(&self) {
+        let uiopts = UiOptions {
+            stdin_fd: **self.stdin_fd.borrow(),
+            ..ui_options()
+        };
+
+        if let Some(path) = self.record_path.take() {
+            if let Err(err) = self.nvim.start_recording(&path) {
+                warn!("failed to start recording to {}: {}", path, err);
+            }
+        }
+
+        if let Some(addr) = self.connect_addr.borrow().clone() {
+            spawn_local!(glib::clone!(
+                #[strong(rename_to = app)]
+                self.obj(),
+                async move {
+                    let imp = app.imp();
+                    match imp.nvim.connect(&addr).await {
+                        Ok(reader) => imp.attach(reader, uiopts),
+                        Err(err) => {
+                            imp.handle_io_error(format!("failed to open {}: {}", addr, err), None)
+                        }
+                    }
+                }
+            ));
+            return;
+        }
+
+        let reader = if let Some(cmd) = self.connect_cmd.take() {
+            self.nvim.open_cmd(&cmd)
+        } else {
+            let args = self.nvim_args.borrow();
+            let args: Vec<&OsStr> = args.iter().map(|a| a.as_ref()).collect();
+            self.nvim.open(&args, uiopts.stdin_fd.is_some())
+        };
+
+        self.attach(reader, uiopts);
+    }
+
+    /// Starts the io loop on `reader`, and attaches the ui.
+    fn attach<R: futures::AsyncRead + Unpin + 'static>(&self, reader: R, uiopts: UiOptions) {
+        let obj = self.obj();
+
+        // Start io loop.
+        spawn_local!(glib::clone!(
+            #[strong(rename_to = app)]
+            obj,
+            async move {
+                app.imp().io_loop(reader).await;
+            }
+        ));
+
+        // Call nvim_ui_attach.
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = nvim)]
+            self.nvim,
+            #[weak]
+            obj,
+            async move {
+                // Fails if we lose the connection meanwhile, in which case
+                // we'll attach again once reconnected.
+                let res = async {
+                    nvim.nvim_set_client_info(
+                        "gnvim",
+                        // TODO(ville): Tell the version in client info.
+                        &dict![],
+                        "ui",
+                        &dict![],
+                        &dict![],
+                    )
+                    .await?;
+
+                    let chan_info = nvim.nvim_get_chan_info(0).await?;
+
+                    // If we're reconnecting, the previous channel is gone.
+                    if let Some(prev) = obj.imp().chan_id.replace(Some(chan_info.id)) {
+                        let _ = nvim
+                            .nvim_del_augroup_by_name(&vimleavepre_augroup(prev))
+                            .await;
+                    }
+
+                    nvim.nvim_exec2(
+                        &format!(
+                            "augroup {}\n\
+                            autocmd!\n\
+                            autocmd VimLeavePre * call rpcrequest({}, 'vimleavepre')\n\
+                            augroup END",
+                            vimleavepre_augroup(chan_info.id),
+                            chan_info.id,
+                        ),
+                        &dict![],
+                    )
+                    .await?;
+
+                    let (cols, rows) = obj.imp().shell.root_grid_size();
+
+                    nvim.nvim_ui_attach(cols as i64, rows as i64, uiopts)
+                        .await?;
+
+                    // Neovim assumes we're focused.
+                    if !obj.imp().shell.focused() {
+                        nvim.nvim_ui_set_focus(false).await?;
+                    }
+
+                    Ok::<_, CallError>(())
+                }
+                .await;
+
+                if let Err(err) = res {
+                    warn!("failed to attach: {}", err);
+                    return;
+                }
+
+                // Install our clipboard provider.
+                obj.imp().call_clipboard_lua("setup").await;
+            }
+        ));
+    }
+
+    /// Keeps trying to connect to `connect-addr` again, with a backoff. Once
+    /// connected, attaches the ui again, which redraws the whole screen.
+    async fn reconnect(&self) {
+        let addr = some_or_return!(self.connect_addr.borrow().clone(), "no address");
+
+        self.set_disconnected(true);
+
+        let mut delay = RECONNECT_DELAY_MIN;
+        let reader = loop {
+            glib::timeout_future(delay).await;
+
+            match self.nvim.connect(&addr).await {
+                Ok(reader) => break reader,
+                Err(err) => {
+                    debug!("failed to reconnect to {}: {}", addr, err);
+                    delay = (delay * 2).min(RECONNECT_DELAY_MAX);
+                }
+            }
+        };
+
+        debug!("reconnected to {}", addr);
+        self.set_disconnected(false);
+
+        self.attach(reader, ui_options());
+    }
+
+    /// Calls `gnvim.clipboard.<func>` with our channel. Needs our runtime
+    /// files, which servers we connect to might not have.
+    async fn call_clipboard_lua(&self, func: &str) {
+        let Some(chan) = self.chan_id.get() else {
+            // Not attached yet.
+            return;
+        };
+
+        if let Err(err) = self
+            .nvim
+            .nvim_exec_lua(
+                &format!(
+                    "local ok, gnvim = pcall(require, 'gnvim')\n\
+                    if ok then gnvim.clipboard.{}(...) end",
+                    func
+                ),
+                vec![chan.into()],
+            )
+            .await
+        {
+            warn!("gnvim.clipboard.{} failed: {}", func, err);
+        }
+    }
+
+    fn clipboard(&self, selection: &str) -> Result<gdk::Clipboard, NvimError> {
+        let display = WidgetExt::display(&*self.obj());
+        match selection {
+            "clipboard" => Ok(display.clipboard()),
+            "primary" => Ok(display.primary_clipboard()),
+            _ => Err(NvimError::new(
+                ErrorType::Validation,
+                format!("unknown selection: {}", selection),
+            )),
+        }
+    }
+
+    /// Sets the clipboard for the `clipboard_set` request from our
+    /// clipboard provider.
+    fn clipboard_set(
+        &self,
+        selection: &str,
+        lines: Vec<String>,
+        regtype: String,
+    ) -> Result<(), NvimError> {
+        let clipboard = self.clipboard(selection)?;
+
+        let mut text = lines.join("\n");
+        // Linewise content ends in a newline, like it would when copied
+        // from other applications.
+        if regtype == "V" {
+            text.push('\n');
+        }
+
+        clipboard.set_text(&text);
+        self.clipboard_regtypes
+            .borrow_mut()
+            .insert(selection.to_owned(), regtype);
+
+        Ok(())
+    }
+
+    /// Reads the clipboard for the `clipboard_get` request from our
+    /// clipboard provider. Returns the lines, and the register type.
+    async fn clipboard_get(&self, selection: &str) -> Result<(Vec<String>, String), NvimError> {
+        let clipboard = self.clipboard(selection)?;
+        let text = clipboard
+            .read_text_future()
+            .await
+            .map_err(|err| NvimError::new(ErrorType::Exception, err.to_string()))?
+            .map(String::from)
+            .unwrap_or_default();
+
+        // Our own content keeps its register type. Otherwise, content ending
+        // in a newline is linewise.
+        let regtype = clipboard
+            .is_local()
+            .then(|| self.clipboard_regtypes.borrow().get(selection).cloned())
+            .flatten()
+            .unwrap_or_else(|| {
+                if text.ends_with('\n') {
+                    String::from("V")
+                } else {
+                    String::from("v")
+                }
+            });
+
+        let text = match regtype.as_str() {
+            "V" => text.strip_suffix('\n').unwrap_or(&text),
+            _ => &text,
+        };
+        let lines = text
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line).to_owned())
+            .collect();
+
+        Ok((lines, regtype))
+    }
+
+    /// Opens `file`, and waits until its buffer is closed. Returns neovim's
+    /// exit code if neovim exited first (e.g. non-zero for `:cq`), zero
+    /// otherwise. Returns `None` if the file couldn't be opened, or the window
+    /// was closed before the buffer.
+    pub async fn edit_and_wait(&self, file: &gio::File) -> Option<i32> {
+        let obj = self.obj();
+        let path = obj.nvim_path(file);
+
+        let id = self.next_wait_id.get();
+        self.next_wait_id.set(id + 1);
+        let (tx, rx) = oneshot::channel();
+        self.waits.borrow_mut().insert(id, tx);
+
+        let res = async {
+            obj.edit("edit", path).await?;
+
+            let info = self.nvim.nvim_get_api_info().await?;
+            let chan = info.first().and_then(rmpv::Value::as_i64).unwrap_or(0);
+
+            self.nvim
+                .nvim_exec_lua(WAIT_LUA, vec![chan.into(), id.into()])
+                .await
+        }
+        .await;
+
+        if let Err(err) = res {
+            warn!("failed to open {}: {}", file.uri(), err);
+            self.waits.borrow_mut().remove(&id);
+            return None;
+        }
+
+        rx.await.ok()
+    }
+
+    /// Shows (or hides) that we've lost the connection to neovim, and are
+    /// trying to reconnect.
+    fn set_disconnected(&self, disconnected: bool) {
+        self.disconnected.set(disconnected);
+
+        if disconnected {
+            self.nvim.disconnect();
+            self.shell.add_css_class("disconnected");
+        } else {
+            self.shell.remove_css_class("disconnected");
+        }
+
+        self.banner.set_revealed(disconnected);
+    }
+
+    /// Detaches the ui from neovim, leaving it running, and closes the
+    /// window.
+    fn detach(&self) {
+        if self.connect_addr.borrow().is_none() {
+            warn!("can only detach from a neovim server");
+            return;
+        }
+
+        self.detached.set(true);
+
+        // If we're disconnected, there's nothing to detach from.
+        if self.disconnected.get() {
+            self.obj().close();
+            return;
+        }
+
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = nvim)]
+            self.nvim,
+            #[weak(rename_to = obj)]
+            self.obj(),
+            async move {
+                if let Err(err) = nvim.nvim_ui_detach().await {
+                    warn!("nvim_ui_detach failed: {}", err);
+                }
+
+                obj.close();
+            }
+        ));
+    }
+
+    /// Asks whether to detach from neovim, or to quit it, when closing a
+    /// window connected to a neovim server.
+    fn ask_close(&self) {
+        const CANCEL: &str = "cancel";
+        const DETACH: &str = "detach";
+        const QUIT: &str = "quit";
+
+        let dialog = adw::AlertDialog::builder()
+            .heading("Close Window?")
+            .body("Neovim is running as a server. Detach to leave it running, or quit it.")
+            .close_response(CANCEL)
+            .default_response(DETACH)
+            .build();
+        dialog.add_responses(&[
+            (CANCEL, "Cancel"),
+            (DETACH, "Detach"),
+            (QUIT, "Quit Neovim"),
+        ]);
+        dialog.set_response_appearance(QUIT, adw::ResponseAppearance::Destructive);
+
+        let obj = self.obj();
+        dialog.connect_response(
+            None,
+            glib::clone!(
+                #[weak]
+                obj,
+                move |_, response| match response {
+                    DETACH => obj.imp().detach(),
+                    QUIT => {
+                        spawn_local!(glib::clone!(
+                            #[weak]
+                            obj,
+                            async move {
+                                // Once neovim exits, the io loop closes the
+                                // window.
+                                if let Err(err) = obj.nvim().nvim_command("confirm qall").await {
+                                    warn!("failed to quit neovim: {}", err);
+                                }
+                            }
+                        ));
+                    }
+                    _ => {}
+                }
+            ),
+        );
+
+        dialog.present(Some(obj.upcast_ref::<gtk::Widget>()));
+    }
+
+    /// Replays a recorded session instead of running neovim.
+    fn start_replay(&self, path: String) {
+        self.nvim.open_replay();
+
+        spawn_local!(glib::clone!(
+            #[strong(rename_to = app)]
+            self.obj(),
+            async move {
+                app.imp().replay(&path).await;
+            }
+        ));
+    }
+
+    /// Feeds the messages from neovim in a session recorded with `--record`
+    /// through the same path as live messages. Only notifications are
+    /// replayed, since there's no neovim to respond to us (or for us to
+    /// respond to).
+    async fn replay(&self, path: &str) {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) => {
+                self.handle_io_error(format!("failed to open {}: {}", path, err), None);
+                return;
+            }
+        };
+
+        let fast = self.replay_fast.get();
+        let start = Instant::now();
+
+        for entry in record::read_entries(BufReader::new(file)) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    self.handle_io_error(err, None);
+                    return;
+                }
+            };
+
+            if entry.direction != Direction::Incoming {
+                continue;
+            }
+
+            if fast {
+                // Yield with a low priority, so that gtk gets to draw the
+                // frames in between.
+                glib::timeout_future_with_priority(glib::Priority::LOW, Duration::ZERO).await;
+            } else if let Some(wait) = entry.time.checked_sub(start.elapsed()) {
+                glib::timeout_future(wait).await;
+            }
+
+            match LazyMessage::from_slice(&entry.data) {
+                Ok(LazyMessage::Redraw(redraw)) => self.handle_redraw(redraw),
+                Ok(LazyMessage::Message(msg @ Message::Notification(_))) => {
+                    self.process_nvim_event(msg)
+                }
+                Ok(LazyMessage::Message(_)) => {}
+                Err(err) => {
+                    self.handle_io_error(err, None);
+                    return;
+                }
+            }
+        }
+
+        let elapsed = start.elapsed();
+        if fast {
+            // Printed regardless of the log level, since timing the replay is
+            // the point of `--replay-fast`.
+            eprintln!("replay finished in {:?}", elapsed);
+        } else {
+            debug!("replay finished in {:?}", elapsed);
+        }
+    }
+
     fn handle_hl_group_set(&self, event: HlGroupSet) {
         if let Some(group) = match event.name.as_ref() {
+            "ErrorMsg" => Some(HlGroup::ErrorMsg),
+            "MsgArea" => Some(HlGroup::MsgArea),
             "MsgSeparator" => Some(HlGroup::MsgSeparator),
             "Pmenu" => Some(HlGroup::Pmenu),
             "PmenuSel" => Some(HlGroup::PmenuSel),
@@ -277,6 +906,7 @@ impl AppWindow {
             "TabLine" => Some(HlGroup::TabLine),
             "TabLineFill" => Some(HlGroup::TabLineFill),
             "TabLineSel" => Some(HlGroup::TabLineSel),
+            "WarningMsg" => Some(HlGroup::WarningMsg),
             _ => None,
         } {
             self.colors.borrow_mut().set_hl_group(group, event.id);
@@ -320,6 +950,137 @@ impl AppWindow {
         }
     }
 
+    /// Sets the window's icon name based on the current filetype and
+    /// 'iconstring'.
+    fn update_icon(&self) {
+        let icon = self.icon.borrow();
+        let filetype = self.filetype.borrow();
+        let iconstring = self.iconstring.borrow();
+
+        let obj = self.obj();
+        let theme = gtk::IconTheme::for_display(&WidgetExt::display(&*obj));
+        let name = icon
+            .filetypes
+            .get(&*filetype)
+            .or_else(|| icon.iconstrings.get(&*iconstring))
+            .map(String::as_str)
+            // Use the iconstring as is, if there is such icon.
+            .or_else(|| Some(iconstring.as_str()).filter(|name| theme.has_icon(name)))
+            .filter(|name| !name.is_empty());
+
+        debug!("setting icon name to {:?}", name);
+        obj.set_icon_name(Some(name.unwrap_or(APPID)));
+    }
+
+    /// Minimizes the window, or runs the user's suspend command.
+    fn suspend(&self) {
+        let obj = self.obj();
+        let Some(command) = self.suspend.borrow().command.clone() else {
+            obj.minimize();
+            return;
+        };
+
+        let p = match gio::Subprocess::newv(
+            &[OsStr::new("sh"), OsStr::new("-c"), OsStr::new(&command)],
+            gio::SubprocessFlags::NONE,
+        ) {
+            Ok(p) => p,
+            Err(err) => {
+                warn!("failed to run suspend command: {}", err);
+                return;
+            }
+        };
+
+        spawn_local!(glib::clone!(
+            #[weak]
+            obj,
+            async move {
+                if let Err(err) = p.wait_future().await {
+                    warn!("suspend command failed: {}", err);
+                }
+
+                // Return focus to us.
+                obj.present();
+            }
+        ));
+    }
+
+    fn update_menu(&self) {
+        let obj = self.obj();
+        spawn_local!(glib::clone!(
+            #[weak]
+            obj,
+            async move {
+                let imp = obj.imp();
+                let menus = match imp
+                    .nvim
+                    .call::<Vec<menu::Menu>, _, _>("nvim_call_function", ("menu_get", [""]))
+                    .await
+                {
+                    Ok(menus) => menus,
+                    Err(err) => {
+                        warn!("menu_get failed: {}", err);
+                        return;
+                    }
+                };
+
+                let menubar = menu::menubar(&menus, "win.emenu");
+                imp.menubar.set_visible(menubar.n_items() > 0);
+                imp.menubar.set_menu_model(Some(&menubar));
+
+                imp.shell.set_context_menu(
+                    menu::popup(&menus, "win.emenu").map(|menu| menu.upcast::<gio::MenuModel>()),
+                );
+            }
+        ));
+    }
+
+    fn emenu(&self, path: String) {
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = nvim)]
+            self.nvim,
+            async move {
+                if let Err(err) = nvim.nvim_command(&format!("emenu {}", path)).await {
+                    warn!("emenu failed: {}", err);
+                }
+            }
+        ));
+    }
+
+    fn bell(&self, visual: bool) {
+        let obj = self.obj();
+        let bell = self.bell.borrow();
+
+        if visual || bell.visual {
+            let fg = self.colors.borrow().fg;
+            self.shell.visual_bell(
+                gdk::RGBA::new(fg.red(), fg.green(), fg.blue(), 0.2),
+                Duration::from_millis(bell.duration.max(0.0) as u64),
+            );
+        } else if bell.audible {
+            WidgetExt::display(&*obj).beep();
+        }
+
+        if bell.urgent && !obj.is_active() {
+            self.set_urgency_hint(true);
+        }
+    }
+
+    fn set_urgency_hint(&self, urgent: bool) {
+        self.urgent.set(urgent);
+
+        cfg_if::cfg_if! {
+            if #[cfg(target_os = "linux")] {
+                if let Some(surface) = self.obj().surface().and_downcast::<gdk_x11::X11Surface>() {
+                    surface.set_urgency_hint(urgent);
+                    return;
+                }
+            }
+        }
+
+        debug!("urgency hint not supported");
+    }
+
     fn handle_gnvim_event(&self, event: GnvimEvent) {
         match event {
             GnvimEvent::EchoRepeat(echo_repeat) => {
@@ -332,15 +1093,16 @@ impl AppWindow {
                     #[weak(rename_to = nvim)]
                     self.nvim,
                     async move {
-                        nvim.nvim_echo(msg, false, &dict![])
-                            .await
-                            .expect("nvim_echo failed");
+                        if let Err(err) = nvim.nvim_echo(msg, false, &dict![]).await {
+                            warn!("nvim_echo failed: {}", err);
+                        }
                     }
                 ));
             }
             GnvimEvent::GtkDebugger => {
                 self.enable_debugging(true);
             }
+            GnvimEvent::Detach => self.detach(),
             GnvimEvent::Setup(event) => {
                 let obj = self.obj();
                 obj.set_cursor_position_transition(event.cursor.position_transition.max(0.0));
@@ -351,6 +1113,15 @@ impl AppWindow {
                     event.popupmenu.kinds,
                     &self.colors.borrow(),
                 ));
+
+                self.bell.replace(event.bell);
+                self.icon.replace(event.icon);
+                self.suspend.replace(event.suspend);
+                self.update_icon();
+            }
+            GnvimEvent::Filetype(event) => {
+                self.filetype.replace(event.filetype);
+                self.update_icon();
             }
             GnvimEvent::FontSize(event) => {
                 let font = self.font.borrow();
@@ -364,13 +1135,16 @@ impl AppWindow {
                     #[weak(rename_to = nvim)]
                     self.nvim,
                     async move {
-                        nvim.nvim_set_option_value(
-                            "guifont",
-                            &nvim::types::Object::from(guifont),
-                            &dict![],
-                        )
-                        .await
-                        .expect("nvim_set_option for guifont failed");
+                        if let Err(err) = nvim
+                            .nvim_set_option_value(
+                                "guifont",
+                                &nvim::types::Object::from(guifont),
+                                &dict![],
+                            )
+                            .await
+                        {
+                            warn!("nvim_set_option for guifont failed: {}", err);
+                        }
                     }
                 ));
             }
@@ -383,7 +1157,10 @@ impl AppWindow {
             UiEvent::SetTitle(events) => events.into_iter().for_each(|event| {
                 self.obj().set_title(Some(&event.title));
             }),
-            UiEvent::SetIcon(_) => {}
+            UiEvent::SetIcon(events) => events.into_iter().for_each(|event| {
+                self.iconstring.replace(event.icon);
+                self.update_icon();
+            }),
             UiEvent::ModeInfoSet(events) => events.into_iter().for_each(|event| {
                 self.mode_infos
                     .replace(event.cursor_styles.into_iter().map(Into::into).collect());
@@ -398,19 +1175,21 @@ impl AppWindow {
                     .expect("invalid mode_idx");
                 self.shell.handle_mode_change(mode);
             }),
-            UiEvent::MouseOn => {}
-            UiEvent::MouseOff => {}
+            UiEvent::MouseOn => self.shell.set_mouse_enabled(true),
+            UiEvent::MouseOff => self.shell.set_mouse_enabled(false),
             UiEvent::BusyStart => {
                 self.shell.busy_start();
             }
             UiEvent::BusyStop => {
                 self.shell.busy_stop();
             }
-            UiEvent::Suspend => {}
-            UiEvent::UpdateMenu => {}
-            UiEvent::Bell => {}
-            UiEvent::VisualBell => {}
-            UiEvent::Chdir(_) => {}
+            UiEvent::Suspend => self.suspend(),
+            UiEvent::UpdateMenu => self.update_menu(),
+            UiEvent::Bell => self.bell(false),
+            UiEvent::VisualBell => self.bell(true),
+            UiEvent::Chdir(events) => events
+                .into_iter()
+                .for_each(|event| self.obj().set_cwd(event.path)),
             UiEvent::Flush => {
                 self.shell.handle_flush(&self.colors.borrow());
                 self.tabline.flush();
@@ -427,6 +1206,9 @@ impl AppWindow {
                     let pmenu_thumb = colors.get_hl_group(&HlGroup::PmenuThumb);
                     let pmenu_bar = colors.get_hl_group(&HlGroup::PmenuSbar);
                     let msgsep = colors.get_hl_group(&HlGroup::MsgSeparator);
+                    let msgarea = colors.get_hl_group(&HlGroup::MsgArea);
+                    let errormsg = colors.get_hl_group(&HlGroup::ErrorMsg);
+                    let warningmsg = colors.get_hl_group(&HlGroup::WarningMsg);
                     let tablinefill = colors.get_hl_group(&HlGroup::TabLineFill);
                     let tabline = colors.get_hl_group(&HlGroup::TabLine);
                     let tablinesel = colors.get_hl_group(&HlGroup::TabLineSel);
@@ -439,6 +1221,10 @@ impl AppWindow {
                         bg = colors.bg.as_hex(),
                         fg = colors.fg.as_hex(),
                         msgsep = msgsep.fg().as_hex(),
+                        msgarea_fg = msgarea.fg().as_hex(),
+                        msgarea_bg = msgarea.bg().as_hex(),
+                        errormsg = errormsg.fg().as_hex(),
+                        warningmsg = warningmsg.fg().as_hex(),
                         pmenu_fg = pmenu.fg().as_hex(),
                         pmenu_bg = pmenu.bg().as_hex(),
                         pmenu_sel_fg = pmenu_sel.fg().as_hex(),
@@ -471,9 +1257,8 @@ impl AppWindow {
             UiEvent::HlGroupSet(events) => events.into_iter().for_each(|event| {
                 self.handle_hl_group_set(event);
             }),
-            UiEvent::GridLine(events) => events.into_iter().for_each(|event| {
-                self.shell.handle_grid_line(event);
-            }),
+            // Decoded straight into the grids by `handle_redraw`.
+            UiEvent::GridLine(_) => warn!("grid_line should've been handled in handle_redraw"),
             UiEvent::GridClear(events) => events.into_iter().for_each(|event| {
                 self.shell.handle_grid_clear(event);
             }),
@@ -550,11 +1335,39 @@ impl AppWindow {
                 .into_iter()
                 .for_each(|event| self.cmdline.block_append(event, &self.colors.borrow())),
 
+            // message events
+            UiEvent::MsgShow(events) => events
+                .into_iter()
+                .for_each(|event| self.messages.show(event, &self.colors.borrow())),
+            UiEvent::MsgClear => self.messages.clear(),
+            UiEvent::MsgShowmode(events) => events
+                .into_iter()
+                .for_each(|event| self.msgstrip.showmode(event, &self.colors.borrow())),
+            UiEvent::MsgShowcmd(events) => events
+                .into_iter()
+                .for_each(|event| self.msgstrip.showcmd(event, &self.colors.borrow())),
+            UiEvent::MsgRuler(events) => events
+                .into_iter()
+                .for_each(|event| self.msgstrip.ruler(event, &self.colors.borrow())),
+            UiEvent::MsgHistoryShow(events) => events
+                .into_iter()
+                .for_each(|event| self.messages.history_show(event, &self.colors.borrow())),
+
             UiEvent::Restart(events) => events.into_iter().for_each(|event| {
                 self.obj().set_pending_restart_addr(event.listen_addr);
             }),
 
-            event => panic!("Unhandled ui event: {}", event),
+            UiEvent::Unknown { name, .. } => self.warn_unhandled_event(name, "unknown"),
+            event => self.warn_unhandled_event(event.to_string(), "unhandled"),
+        }
+    }
+
+    /// Warns about unhandled ui event, once per event name.
+    fn warn_unhandled_event(&self, name: String, reason: &str) {
+        let mut events = self.unhandled_events.borrow_mut();
+        if !events.contains(&name) {
+            warn!("{} ui event: {}", reason, name);
+            events.insert(name);
         }
     }
 
@@ -588,11 +1401,10 @@ impl AppWindow {
     }
 
     async fn send_nvim_input(&self, input: String) {
-        self.nvim
-            .nvim_input(&input)
-            .await
-            // TODO(ville): nvim_input handle the returned bytes written value.
-            .expect("nvim_input failed");
+        // TODO(ville): nvim_input handle the returned bytes written value.
+        if let Err(err) = self.nvim.nvim_input(&input).await {
+            warn!("nvim_input failed: {}", err);
+        }
     }
 
     fn save_window_state(&self) -> Result<(), glib::BoolError> {
@@ -638,6 +1450,16 @@ impl AppWindow {
 
 #[gtk::template_callbacks]
 impl AppWindow {
+    #[template_callback(function)]
+    fn cwd_display(cwd: &str) -> String {
+        let home = glib::home_dir();
+        match std::path::Path::new(cwd).strip_prefix(&home) {
+            Ok(rest) if rest.as_os_str().is_empty() => String::from("~"),
+            Ok(rest) => format!("~/{}", rest.to_string_lossy()),
+            Err(_) => cwd.to_owned(),
+        }
+    }
+
     #[template_callback]
     async fn im_commit(&self, input: &str) {
         // NOTE(ville): "<" needs to be escaped for nvim_input (see `:h nvim_input`)
@@ -711,6 +1533,29 @@ impl ObjectSubclass for AppWindow {
     fn class_init(klass: &mut Self::Class) {
         Shell::ensure_type();
         Tabline::ensure_type();
+        Messages::ensure_type();
+        MsgStrip::ensure_type();
+
+        klass.install_action(
+            "win.emenu",
+            Some(glib::VariantTy::STRING),
+            |obj, _, param| {
+                let path = some_or_return!(
+                    param.and_then(|param| param.get::<String>()),
+                    "invalid emenu param"
+                );
+                obj.imp().emenu(path);
+            },
+        );
+
+        klass.install_action("win.detach", None, |obj, _, _| {
+            obj.imp().detach();
+        });
+        klass.add_binding_action(
+            gdk::Key::D,
+            gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK,
+            "win.detach",
+        );
 
         klass.bind_template();
         klass.bind_template_callbacks();
@@ -727,76 +1572,22 @@ impl ObjectImpl for AppWindow {
         self.parent_constructed();
         let obj = self.obj();
 
+        self.handler.replace(Rc::new(self.create_handler()));
+
         gtk::style_context_add_provider_for_display(
             &gdk::Display::default().expect("couldn't get display"),
             &self.css_provider,
             gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
         );
 
-        let uiopts = UiOptions {
-            rgb: true,
-            ext_linegrid: true,
-            ext_multigrid: true,
-            ext_popupmenu: true,
-            ext_tabline: true,
-            ext_cmdline: true,
-            stdin_fd: **self.stdin_fd.borrow(),
-            ..Default::default()
-        };
-        let reader = if let Some(addr) = self.connect_addr.take() {
-            self.nvim.connect(&addr)
+        if let Some(path) = self.replay_path.take() {
+            self.start_replay(path);
         } else {
-            let args = self.nvim_args.borrow();
-            let args: Vec<&OsStr> = args.iter().map(|a| a.as_ref()).collect();
-            self.nvim.open(&args, uiopts.stdin_fd.is_some())
-        };
-
-        // Start io loop.
-        spawn_local!(glib::clone!(
-            #[strong(rename_to = app)]
-            obj,
-            async move {
-                app.imp().io_loop(reader).await;
-            }
-        ));
-
-        // Call nvim_ui_attach.
-        spawn_local!(glib::clone!(
-            #[weak(rename_to = nvim)]
-            self.nvim,
-            #[weak]
-            obj,
-            async move {
-                nvim.nvim_set_client_info(
-                    "gnvim",
-                    // TODO(ville): Tell the version in client info.
-                    &dict![],
-                    "ui",
-                    &dict![],
-                    &dict![],
-                )
-                .await
-                .expect("nvim_set_client_info failed");
-
-                let chan_info = nvim
-                    .nvim_get_chan_info(0)
-                    .await
-                    .expect("nvim_get_chan_info failed");
-
-                nvim.nvim_command(&format!(
-                    "autocmd VimLeavePre * call rpcrequest({}, 'vimleavepre')",
-                    chan_info.id,
-                ))
-                .await
-                .expect("nvim_command failed");
-
-                let (cols, rows) = obj.imp().shell.root_grid_size();
+            self.start_nvim();
+        }
 
-                nvim.nvim_ui_attach(cols as i64, rows as i64, uiopts)
-                    .await
-                    .expect("nvim_ui_attach failed");
-            }
-        ));
+        // Detaching only makes sense for neovim servers.
+        obj.action_set_enabled("win.detach", self.connect_addr.borrow().is_some());
 
         // TODO(ville): Figure out if we should use preedit or not.
         self.im_context.borrow().set_use_preedit(false);
@@ -807,6 +1598,29 @@ impl ObjectImpl for AppWindow {
 
         obj.add_controller(self.event_controller_key.borrow().clone());
 
+        obj.connect_is_active_notify(|obj| {
+            let imp = obj.imp();
+            // Clear the urgency hint once focused.
+            if obj.is_active() && imp.urgent.get() {
+                imp.set_urgency_hint(false);
+            }
+
+            imp.shell.update_focus();
+        });
+
+        // Our clipboard provider uses the gnvim ui that was focused last.
+        self.shell.connect_focused_notify(glib::clone!(
+            #[weak]
+            obj,
+            move |shell| {
+                if shell.focused() {
+                    spawn_local!(async move {
+                        obj.imp().call_clipboard_lua("focus").await;
+                    });
+                }
+            }
+        ));
+
         self.load_window_state();
     }
 
@@ -824,10 +1638,18 @@ impl WidgetImpl for AppWindow {}
 
 impl WindowImpl for AppWindow {
     fn close_request(&self) -> glib::Propagation {
+        if self.connect_addr.borrow().is_some() && !self.detached.get() && !self.nvim_exited.get() {
+            self.ask_close();
+            return glib::Propagation::Stop;
+        }
+
         if let Err(err) = self.save_window_state() {
             warn!("failed to save window state: {}", err);
         }
 
+        // Release anyone still waiting on us.
+        self.waits.borrow_mut().clear();
+
         self.parent_close_request()
     }
 }
@@ -1,10 +1,12 @@
 mod imp;
 
+use std::path::{Path, PathBuf};
+
 use adw;
-use gtk::{gio, glib, prelude::*};
-use nvim::NeovimApi;
+use gtk::{gio, glib, prelude::*, subclass::prelude::*};
+use nvim::{dict, rpc::CallError, NeovimApi};
 
-use crate::{debug, spawn_local};
+use crate::{debug, spawn_local, warn};
 
 glib::wrapper! {
     pub struct AppWindow(ObjectSubclass<imp::AppWindow>)
@@ -13,22 +15,211 @@ glib::wrapper! {
                     gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
 }
 
+/// How much to read at once in `AppWindow::open_stream`.
+const STREAM_CHUNK: usize = 64 * 1024;
+
+/// Takes the complete lines out of `pending`, or everything at `eof`.
+fn take_lines(pending: &mut Vec<u8>, eof: bool) -> Vec<String> {
+    let end = if eof {
+        pending.len()
+    } else {
+        match pending.iter().rposition(|b| *b == b'\n') {
+            Some(i) => i + 1,
+            None => return vec![],
+        }
+    };
+
+    let data = pending.drain(..end).collect::<Vec<_>>();
+    String::from_utf8_lossy(&data)
+        .lines()
+        .map(String::from)
+        .collect()
+}
+
+/// Resolves `path` against neovim's working directory `cwd`. If `cwd` isn't
+/// known yet, relative paths are left for neovim to resolve (against the same
+/// directory).
+fn resolve_path(cwd: &str, path: &Path) -> PathBuf {
+    if cwd.is_empty() {
+        path.to_owned()
+    } else {
+        Path::new(cwd).join(path)
+    }
+}
+
 impl AppWindow {
     pub fn open_files(&self, files: &[gtk::gio::File]) {
-        let nvim = self.nvim();
         for file in files.iter() {
             debug!("opening {}", file.uri());
-            spawn_local!(glib::clone!(
-                #[weak]
-                nvim,
-                #[strong]
-                file,
-                async move {
-                    nvim.nvim_command(&format!("e {}", file.uri()))
+            self.open(self.nvim_path(file));
+        }
+    }
+
+    /// Opens command line arguments `paths`, with relative paths relative to
+    /// neovim's working directory instead of ours. For when neovim isn't
+    /// started by us (e.g. with `--connect`).
+    pub fn open_paths(&self, paths: &[PathBuf]) {
+        let cwd = self.cwd();
+        for path in paths {
+            debug!("opening {}", path.display());
+            let path = resolve_path(&cwd, path);
+            self.open(if path.is_absolute() {
+                self.nvim_path(&gio::File::for_path(path))
+            } else {
+                path.to_string_lossy().into_owned()
+            });
+        }
+    }
+
+    fn open(&self, path: String) {
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                if let Err(err) = obj.edit("edit", path).await {
+                    warn!("nvim_cmd failed: {}", err);
+                }
+            }
+        ));
+    }
+
+    /// Runs `cmd` (e.g. `edit` or `split`) on `path`, taking the path as is
+    /// (i.e. without expanding wildcards and such).
+    pub async fn edit(&self, cmd: &str, path: String) -> Result<(), CallError> {
+        self.nvim()
+            .nvim_cmd(
+                &dict![
+                    "cmd".into() => cmd.into(),
+                    "args".into() => rmpv::Value::from(vec![rmpv::Value::from(path)]),
+                    "magic".into() => rmpv::Value::Map(vec![
+                        ("file".into(), false.into()),
+                    ])
+                ],
+                &dict![],
+            )
+            .await
+            .map(|_| ())
+    }
+
+    /// Reads `stream` into a new scratch buffer, a line at a time as it
+    /// comes in (e.g. for `tail -f | gnvim`).
+    pub fn open_stream(&self, stream: gio::InputStream) {
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = nvim)]
+            self.nvim(),
+            async move {
+                let res = async {
+                    let buf = nvim.nvim_create_buf(true, true).await?;
+                    nvim.nvim_set_current_buf(&buf).await?;
+                    Ok::<_, CallError>(buf)
+                }
+                .await;
+                let buf = match res {
+                    Ok(buf) => buf,
+                    Err(err) => {
+                        warn!("failed to open scratch buffer: {}", err);
+                        return;
+                    }
+                };
+
+                let mut pending = vec![];
+                // The first lines replace the new buffer's empty line.
+                let mut start = 0;
+                loop {
+                    let eof = match stream
+                        .read_bytes_future(STREAM_CHUNK, glib::Priority::DEFAULT)
                         .await
-                        .expect("nvim_command failed");
+                    {
+                        Ok(bytes) => {
+                            pending.extend_from_slice(&bytes);
+                            bytes.is_empty()
+                        }
+                        Err(err) => {
+                            warn!("failed to read stream: {}", err);
+                            true
+                        }
+                    };
+
+                    let lines = take_lines(&mut pending, eof)
+                        .into_iter()
+                        .map(rmpv::Value::from)
+                        .collect::<Vec<_>>();
+                    if !lines.is_empty() {
+                        if let Err(err) =
+                            nvim.nvim_buf_set_lines(&buf, start, -1, false, lines).await
+                        {
+                            // E.g. the buffer was deleted.
+                            warn!("nvim_buf_set_lines failed: {}", err);
+                            return;
+                        }
+                        start = -1;
+                    }
+
+                    if eof {
+                        return;
+                    }
                 }
-            ));
-        }
+            }
+        ));
+    }
+
+    /// Opens `file`, and waits until its buffer is closed. See `--wait`.
+    pub async fn edit_and_wait(&self, file: &gio::File) -> Option<i32> {
+        self.imp().edit_and_wait(file).await
+    }
+
+    /// Path (or uri, for non-local files) to `file` for neovim. Relative to
+    /// neovim's working directory, if possible.
+    pub fn nvim_path(&self, file: &gio::File) -> String {
+        let cwd = self.cwd();
+        let relative = if cwd.is_empty() {
+            None
+        } else {
+            gio::File::for_path(cwd).relative_path(file)
+        };
+
+        relative
+            .or_else(|| file.path())
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| file.uri().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{resolve_path, take_lines};
+
+    #[test]
+    fn test_resolve_path() {
+        assert_eq!(
+            resolve_path("/home/user/project", Path::new("src/main.rs")),
+            PathBuf::from("/home/user/project/src/main.rs")
+        );
+        assert_eq!(
+            resolve_path("/home/user/project", Path::new("/etc/hosts")),
+            PathBuf::from("/etc/hosts")
+        );
+        assert_eq!(
+            resolve_path("", Path::new("src/main.rs")),
+            PathBuf::from("src/main.rs")
+        );
+    }
+
+    #[test]
+    fn test_take_lines() {
+        let mut pending = b"foo\nbar\r\nba".to_vec();
+        assert_eq!(take_lines(&mut pending, false), vec!["foo", "bar"]);
+        assert_eq!(pending, b"ba");
+
+        assert!(take_lines(&mut pending, false).is_empty());
+        pending.extend_from_slice(b"z\n\n");
+        assert_eq!(take_lines(&mut pending, false), vec!["baz", ""]);
+        assert!(pending.is_empty());
+
+        pending.extend_from_slice(b"qux");
+        assert_eq!(take_lines(&mut pending, true), vec!["qux"]);
+        assert!(pending.is_empty());
     }
 }
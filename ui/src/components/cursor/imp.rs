@@ -37,6 +37,8 @@ pub struct Cursor {
     pub double_width: Cell<bool>,
 
     pub node: RefCell<Option<gsk::RenderNode>>,
+    /// Hollow block drawn instead of `node` when we're not focused.
+    pub outline: RefCell<Option<gsk::RenderNode>>,
 
     #[property(set)]
     pub shape: RefCell<CursorShape>,
@@ -50,6 +52,9 @@ pub struct Cursor {
     pub active: Cell<bool>,
     #[property(get, set, default = false)]
     pub busy: Cell<bool>,
+    /// If our window has the focus.
+    #[property(get, set, default = false)]
+    pub focused: Cell<bool>,
 
     #[property(get, set)]
     pub font: RefCell<Font>,
@@ -87,7 +92,8 @@ impl Cursor {
 
         let new_id = self.obj().add_tick_callback(|this, clock| {
             let imp = this.imp();
-            if imp.active.get() || !imp.busy.get() {
+            // Blinking is paused while we're not focused.
+            if imp.focused.get() && (imp.active.get() || !imp.busy.get()) {
                 if let Some(ref mut blink) = imp.blink.borrow_mut().as_mut() {
                     blink.tick(clock.frame_time() as f64);
                 }
@@ -111,6 +117,16 @@ impl ObjectImpl for Cursor {
         self.obj().connect_font_notify(|this| this.queue_draw());
         self.obj().connect_active_notify(|this| this.queue_draw());
         self.obj().connect_busy_notify(|this| this.queue_draw());
+        self.obj().connect_focused_notify(|this| {
+            // Start from a visible cursor when the focus comes back.
+            if let (Some(ref mut blink), Some(clock)) =
+                (this.imp().blink.borrow_mut().as_mut(), this.frame_clock())
+            {
+                blink.reset_to_wait(clock.frame_time() as f64);
+            }
+
+            this.queue_draw();
+        });
 
         self.obj().connect_blink_notify(|this| {
             // Clear the cached render node, since blink directly contributes to it.
@@ -125,6 +141,19 @@ impl WidgetImpl for Cursor {
             return;
         }
 
+        if !self.focused.get() {
+            if let Some(ref outline) = *self.outline.borrow() {
+                let pos = self.pos.borrow();
+                snapshot.translate(&graphene::Point::new(
+                    pos.pos.0 as f32,
+                    (pos.pos.1 + pos.y_offset) as f32,
+                ));
+                snapshot.append_node(outline);
+            }
+
+            return;
+        }
+
         if let Some(ref node) = *self.node.borrow() {
             let pos = self.pos.borrow();
             snapshot.translate(&graphene::Point::new(
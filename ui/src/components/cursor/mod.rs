@@ -1,4 +1,4 @@
-use gtk::{glib, gsk, prelude::*, subclass::prelude::*};
+use gtk::{glib, graphene, gsk, prelude::*, subclass::prelude::*};
 
 use crate::{colors::Colors, math::ease_out_cubic, some_or_return, warn, SCALE};
 
@@ -49,6 +49,20 @@ impl Cursor {
 
         let bg_node = gsk::ColorNode::new(bg, &rect).upcast();
 
+        // When unfocused, we draw a hollow block over the whole cell.
+        let cell = graphene::Rect::new(
+            0.0,
+            0.0,
+            if double { width * 2.0 } else { width } / SCALE,
+            height / SCALE,
+        );
+        let outline = gsk::BorderNode::new(
+            &gsk::RoundedRect::from_rect(cell, 0.0),
+            &[1.0; 4],
+            &[**bg; 4],
+        );
+        imp.outline.replace(Some(outline.upcast()));
+
         let attrs = crate::render::create_hl_attrs(&hl, &font);
         let fg_node = crate::render::render_text(
             &self.pango_context(),
@@ -1,7 +1,8 @@
 use std::cell::{Ref, RefMut};
 
 use gtk::{glib, graphene, gsk, prelude::*, subclass::prelude::*};
-use nvim::types::uievents::{GridLine, GridScroll};
+use nvim::rpc::{redraw::DecodeError, GridLineRef};
+use nvim::types::uievents::GridScroll;
 
 use crate::colors::Colors;
 
@@ -42,11 +43,11 @@ impl GridBuffer {
         self.imp().rows.borrow_mut()
     }
 
-    pub fn update_row(&self, event: &GridLine) {
+    pub fn update_row(&self, event: &GridLineRef<'_>) -> Result<(), DecodeError> {
         let mut rows = self.get_rows_mut();
         let row = rows.get_mut(event.row as usize).expect("invalid row");
 
-        let n = row.update(event);
+        let n = row.update(event)?;
 
         // Invalidate the margin nodes if needed.
         let left = event.col_start;
@@ -64,6 +65,8 @@ impl GridBuffer {
         }
 
         self.set_dirty(true);
+
+        Ok(())
     }
 
     pub fn resize(&self, width: usize, height: usize) {
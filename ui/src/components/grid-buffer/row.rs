@@ -6,7 +6,7 @@ use std::{
 
 use gtk::{graphene, gsk, pango, prelude::*};
 
-use nvim::types::uievents::GridLine;
+use nvim::rpc::{redraw::DecodeError, GridLineRef, GridLineVisitor};
 
 use crate::{colors::Colors, font::Font, SCALE};
 
@@ -184,42 +184,19 @@ impl Row {
     /// Update the row content.
     ///
     /// Returns number of cells that were updated.
-    pub fn update(&mut self, event: &GridLine) -> usize {
+    pub fn update(&mut self, event: &GridLineRef<'_>) -> Result<usize, DecodeError> {
         self.clear_render_node();
-        let mut hl_id = event
-            .data
-            .first()
-            .expect("grid line event cant be empty")
-            .hl_id
-            .expect("first item should have hl_id");
 
         let start = event.col_start as usize;
-
-        let mut iter = self.cells.iter_mut().skip(start);
-        let mut data_iter = event.data.iter().peekable();
-        let mut n = 0;
-        while let Some(data) = data_iter.next() {
-            if let Some(id) = data.hl_id {
-                hl_id = id;
-            }
-
-            let double_width = data_iter
-                .peek()
-                .map(|peek| peek.text.is_empty())
-                .unwrap_or(false);
-
-            let repeate = data.repeat.unwrap_or(1) as usize;
-            n += repeate;
-            for _ in 0..repeate {
-                let cell = iter.next().expect("too long grid line event");
-                cell.hl_id = hl_id;
-                cell.text.clone_from(&data.text);
-                cell.double_width = double_width;
-                cell.clear_nodes();
-            }
-        }
-
-        n
+        let mut update = RowUpdate {
+            cells: &mut self.cells,
+            start,
+            col: start,
+            hl_id: None,
+        };
+        event.visit_cells(&mut update)?;
+
+        Ok(update.col - start)
     }
 
     /// Generate the render nodes for the whole row.
@@ -470,3 +447,43 @@ where
         .upcast()
     }
 }
+
+/// Writes the cells of a `grid_line` event to a row.
+struct RowUpdate<'a> {
+    cells: &'a mut [Cell],
+    /// Column where the update started.
+    start: usize,
+    /// Column of the next cell.
+    col: usize,
+    hl_id: Option<i64>,
+}
+
+impl GridLineVisitor for RowUpdate<'_> {
+    fn cell(&mut self, text: &str, hl_id: Option<i64>, repeat: Option<i64>) {
+        if hl_id.is_some() {
+            self.hl_id = hl_id;
+        }
+        let hl_id = self.hl_id.expect("first item should have hl_id");
+
+        // Empty text follows a double width cell.
+        if text.is_empty() && self.col > self.start {
+            self.cells[self.col - 1].double_width = true;
+        }
+
+        for _ in 0..repeat.unwrap_or(1) {
+            let cell = self
+                .cells
+                .get_mut(self.col)
+                .expect("too long grid line event");
+            cell.hl_id = hl_id;
+            if cell.text != text {
+                cell.text.clear();
+                cell.text.push_str(text);
+            }
+            cell.double_width = false;
+            cell.clear_nodes();
+
+            self.col += 1;
+        }
+    }
+}
@@ -2,20 +2,23 @@ use std::cell::{Cell, RefCell};
 
 use gtk::glib::subclass::InitializingObject;
 use gtk::subclass::prelude::*;
+use gtk::{gdk, gio, graphene, gsk};
 use gtk::{
     glib::{self},
     prelude::*,
 };
-use gtk::{graphene, gsk};
 use nvim::types::Window;
 use nvim::NeovimApi;
 
 use crate::boxed::{CursorShape, ModeInfo};
+use crate::colors::Color;
 use crate::components::grid_buffer::ViewportMargins;
 use crate::components::{cursor, Cursor, ExternalWindow, GridBuffer};
 use crate::font::Font;
 use crate::nvim::Neovim;
-use crate::{spawn_local, warn};
+use crate::{spawn_local, warn, SCALE};
+
+use super::Selection;
 
 #[derive(gtk::CompositeTemplate, glib::Properties, Default)]
 #[properties(wrapper_type = super::Grid)]
@@ -75,6 +78,9 @@ pub struct Grid {
     pub active: Cell<bool>,
     #[property(get, set, default = false)]
     pub busy: Cell<bool>,
+    /// If our window (or any other of the ui's windows) has the focus.
+    #[property(get, set, default = false)]
+    pub focused: Cell<bool>,
     #[property(get, set)]
     pub mode_info: RefCell<ModeInfo>,
 
@@ -93,6 +99,24 @@ pub struct Grid {
     /// The scroll animation speed.
     #[property(get, set, minimum = 0.0)]
     pub scroll_transition: Cell<f64>,
+
+    /// If neovim has the mouse enabled. When disabled, mouse events are
+    /// handled on the GUI side.
+    #[property(get, set = Self::set_mouse_enabled, default = true)]
+    pub mouse_enabled: Cell<bool>,
+    /// GUI side selection, used when the mouse is disabled.
+    pub selection: Cell<Option<Selection>>,
+    /// Foreground color from the latest flush.
+    pub fg: Cell<Color>,
+    /// Context menu, shown on right click when the mouse is disabled.
+    #[property(get, set, nullable)]
+    pub context_menu: RefCell<Option<gio::MenuModel>>,
+    pub context_popover: RefCell<Option<gtk::PopoverMenu>>,
+
+    /// Color of the visual bell flash, if currently flashing.
+    pub bell_color: RefCell<Option<gdk::RGBA>>,
+    /// Timeout to end the visual bell flash.
+    pub bell_timeout: RefCell<Option<glib::SourceId>>,
 }
 
 impl Grid {
@@ -102,6 +126,38 @@ impl Grid {
         // Margins affect the scrollbar's size, so queue a resize.
         self.obj().queue_resize();
     }
+
+    fn set_mouse_enabled(&self, enabled: bool) {
+        self.mouse_enabled.set(enabled);
+
+        if self.selection.take().is_some() {
+            self.obj().queue_draw();
+        }
+    }
+
+    fn snapshot_selection(&self, snapshot: &gtk::Snapshot, sel: &Selection) {
+        let font = self.font.borrow();
+        let (width, _) = self.buffer.grid_size();
+
+        let fg = self.fg.get();
+        let color = gdk::RGBA::new(fg.red(), fg.green(), fg.blue(), 0.3);
+
+        for row in sel.rows() {
+            let Some(cols) = sel.cols(row, width) else {
+                continue;
+            };
+
+            snapshot.append_color(
+                &color,
+                &graphene::Rect::new(
+                    font.col_to_x(cols.start as f64) as f32,
+                    font.row_to_y(row as f64) as f32,
+                    font.col_to_x(cols.len() as f64) as f32,
+                    font.height() / SCALE,
+                ),
+            );
+        }
+    }
 }
 
 #[gtk::template_callbacks(functions)]
@@ -185,6 +241,8 @@ impl ObjectImpl for Grid {
             }
         }
 
+        self.mouse_enabled.set(true);
+
         self.gesture_click.set_button(0);
         self.gesture_drag.set_button(0);
         let mut flags = gtk::EventControllerScrollFlags::empty();
@@ -218,10 +276,10 @@ impl ObjectImpl for Grid {
                     match win {
                         Some(win) => {
                             spawn_local!(async move {
-                                obj.nvim()
-                                    .nvim_win_set_cursor(&win, (v, 0))
-                                    .await
-                                    .expect("call to cursor() failed")
+                                if let Err(err) = obj.nvim().nvim_win_set_cursor(&win, (v, 0)).await
+                                {
+                                    warn!("call to cursor() failed: {}", err);
+                                }
                             });
                         }
                         None => {
@@ -231,13 +289,36 @@ impl ObjectImpl for Grid {
                 }
             ));
 
+        // Open dropped files, and paste dropped text.
+        let drop_target = gtk::DropTarget::new(glib::Type::INVALID, gdk::DragAction::COPY);
+        drop_target.set_types(&[gdk::FileList::static_type(), glib::Type::STRING]);
+        drop_target.connect_drop(glib::clone!(
+            #[weak]
+            obj,
+            #[upgrade_or]
+            false,
+            move |target, value, _, _| {
+                if let Ok(files) = value.get::<gdk::FileList>() {
+                    obj.drop_files(files.files(), target.current_event_state());
+                    true
+                } else if let Ok(text) = value.get::<String>() {
+                    obj.drop_text(text);
+                    true
+                } else {
+                    false
+                }
+            }
+        ));
+        obj.add_controller(drop_target);
+
         // Connect mouse events.
         obj.connect_mouse(glib::clone!(
             #[weak]
             obj,
             move |id, mouse, action, modifier, row, col| {
                 spawn_local!(async move {
-                    obj.nvim()
+                    if let Err(err) = obj
+                        .nvim()
                         .nvim_input_mouse(
                             mouse.as_nvim_input(),
                             action.as_nvim_action(),
@@ -247,13 +328,19 @@ impl ObjectImpl for Grid {
                             col as i64,
                         )
                         .await
-                        .expect("nvim_input_mouse failed");
+                    {
+                        warn!("nvim_input_mouse failed: {}", err);
+                    }
                 });
             }
         ))
     }
 
     fn dispose(&self) {
+        if let Some(popover) = self.context_popover.take() {
+            popover.unparent();
+        }
+
         self.dispose_template();
     }
 }
@@ -271,6 +358,17 @@ impl WidgetImpl for Grid {
 
         self.parent_snapshot(snapshot);
 
+        if let Some(sel) = self.selection.get() {
+            self.snapshot_selection(snapshot, &sel);
+        }
+
+        if let Some(color) = self.bell_color.borrow().as_ref() {
+            snapshot.append_color(
+                color,
+                &graphene::Rect::new(0.0, 0.0, req.width() as f32, req.height() as f32),
+            );
+        }
+
         snapshot.pop();
     }
 
@@ -302,5 +400,9 @@ impl WidgetImpl for Grid {
             baseline,
             Some(transform),
         );
+
+        if let Some(popover) = self.context_popover.borrow().as_ref() {
+            popover.present();
+        }
     }
 }
@@ -1,22 +1,27 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
-use gtk::{glib, prelude::*, subclass::prelude::*};
+use gtk::{gdk, gio, glib, prelude::*, subclass::prelude::*};
 
+use nvim::rpc::{redraw::DecodeError, GridLineRef};
 use nvim::types::{
-    uievents::{GridLine, GridResize, GridScroll},
+    uievents::{GridResize, GridScroll},
     Window,
 };
+use nvim::NeovimApi;
 
 use crate::{
     colors::Colors,
     font::Font,
     input::{Action, Mouse},
-    some_or_return,
+    some_or_return, spawn_local, warn,
 };
 
-use super::ExternalWindow;
+use super::{appwindow::AppWindow, ExternalWindow};
 
 mod imp;
+mod selection;
+
+pub use selection::Selection;
 
 glib::wrapper! {
     pub struct Grid(ObjectSubclass<imp::Grid>)
@@ -50,16 +55,51 @@ impl Grid {
         }
     }
 
-    pub fn make_external(&self, parent: &gtk::Window) {
+    /// Moves the grid to its own window. Returns the new window, or `None`
+    /// if the grid was already external.
+    pub fn make_external(&self, parent: &gtk::Window) -> Option<ExternalWindow> {
         if self.imp().external_win.borrow().is_some() {
             // Already external.
-            return;
+            return None;
         }
 
         self.unparent();
         let external = ExternalWindow::new(parent, self);
         external.present();
-        *self.imp().external_win.borrow_mut() = Some(external);
+        *self.imp().external_win.borrow_mut() = Some(external.clone());
+
+        Some(external)
+    }
+
+    pub fn external_window(&self) -> Option<ExternalWindow> {
+        self.imp().external_win.borrow().clone()
+    }
+
+    /// Flash the grid with `color` for `duration`.
+    pub fn visual_bell(&self, color: gdk::RGBA, duration: Duration) {
+        let imp = self.imp();
+
+        if let Some(prev) = imp.bell_timeout.take() {
+            prev.remove();
+        }
+
+        imp.bell_color.replace(Some(color));
+        self.queue_draw();
+
+        let id = glib::timeout_add_local_once(
+            duration,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move || {
+                    let imp = obj.imp();
+                    imp.bell_timeout.take();
+                    imp.bell_color.take();
+                    obj.queue_draw();
+                }
+            ),
+        );
+        imp.bell_timeout.replace(Some(id));
     }
 
     pub fn set_nvim_window(&self, window: Option<Window>) {
@@ -80,6 +120,17 @@ impl Grid {
                 let col = font.scale_to_col(x);
                 let row = font.scale_to_row(y);
 
+                if !obj.mouse_enabled() {
+                    match gst.current_button() {
+                        gdk::BUTTON_PRIMARY => obj.update_selection(action, row, col),
+                        gdk::BUTTON_SECONDARY if matches!(action, Action::Pressed) => {
+                            obj.show_context_menu(x, y)
+                        }
+                        _ => {}
+                    }
+                    return;
+                }
+
                 let modifier = crate::input::modifier_to_nvim(&gst.current_event_state());
                 let mouse = Mouse::from(gst);
 
@@ -139,6 +190,13 @@ impl Grid {
                 if prev.0 != row || prev.1 != col {
                     *prev = (row, col);
 
+                    if !obj.mouse_enabled() {
+                        if gst.current_button() == gdk::BUTTON_PRIMARY {
+                            obj.update_selection(Action::Drag, row, col);
+                        }
+                        return;
+                    }
+
                     let modifier = crate::input::modifier_to_nvim(&gst.current_event_state());
                     let mouse = Mouse::from(gst);
                     f(obj.imp().id.get(), mouse, Action::Drag, modifier, row, col);
@@ -164,6 +222,33 @@ impl Grid {
             || glib::Propagation::Proceed,
             move |evt, dx, dy| {
                 let modifier = crate::input::modifier_to_nvim(&evt.current_event_state());
+
+                if !obj.mouse_enabled() {
+                    // Without the mouse, scroll with plain keys like terminals
+                    // do.
+                    let key = if dx > 0.0 {
+                        "Right"
+                    } else if dx < 0.0 {
+                        "Left"
+                    } else if dy > 0.0 {
+                        "Down"
+                    } else if dy < 0.0 {
+                        "Up"
+                    } else {
+                        return glib::Propagation::Stop;
+                    };
+
+                    let input = format!("<{}{}>", modifier, key);
+                    let nvim = obj.nvim();
+                    spawn_local!(async move {
+                        if let Err(err) = nvim.nvim_input(&input).await {
+                            warn!("nvim_input failed: {}", err);
+                        }
+                    });
+
+                    return glib::Propagation::Stop;
+                }
+
                 let pos = mouse_pos.borrow();
                 let font = obj.font();
                 let col = font.scale_to_col(pos.0);
@@ -186,8 +271,134 @@ impl Grid {
         ));
     }
 
-    pub fn put(&self, event: GridLine) {
-        self.imp().buffer.update_row(&event)
+    /// Opens dropped files in our neovim window. With shift they're opened
+    /// in splits, with control in vertical splits, and with both in tabs.
+    fn drop_files(&self, files: Vec<gio::File>, modifiers: gdk::ModifierType) {
+        let shift = modifiers.contains(gdk::ModifierType::SHIFT_MASK);
+        let ctrl = modifiers.contains(gdk::ModifierType::CONTROL_MASK);
+        let cmd = match (shift, ctrl) {
+            (true, true) => "tabedit",
+            (true, false) => "split",
+            (false, true) => "vsplit",
+            (false, false) => "edit",
+        };
+
+        // Opened like any other files, relative to neovim's working directory.
+        let appwin = some_or_return!(self.app_window(), "no app window for dropped files");
+        let nvim = self.nvim();
+        let win = self.imp().nvim_window.borrow().clone();
+        spawn_local!(async move {
+            // The window might be gone by now.
+            if let Some(win) = win {
+                if let Err(err) = nvim.nvim_set_current_win(&win).await {
+                    warn!("nvim_set_current_win failed: {}", err);
+                }
+            }
+
+            for file in files {
+                if let Err(err) = appwin.edit(cmd, appwin.nvim_path(&file)).await {
+                    warn!("failed to open dropped file {}: {}", file.uri(), err);
+                }
+            }
+        });
+    }
+
+    /// The app window we're in, directly or through our external window.
+    fn app_window(&self) -> Option<AppWindow> {
+        let win = self.root().and_downcast::<gtk::Window>()?;
+        win.transient_for().unwrap_or(win).downcast().ok()
+    }
+
+    /// Pastes dropped text to our neovim window.
+    fn drop_text(&self, text: String) {
+        let nvim = self.nvim();
+        let win = self.imp().nvim_window.borrow().clone();
+        spawn_local!(async move {
+            // The window might be gone by now.
+            if let Some(win) = win {
+                if let Err(err) = nvim.nvim_set_current_win(&win).await {
+                    warn!("nvim_set_current_win failed: {}", err);
+                }
+            }
+
+            // Fails for e.g. 'nomodifiable' buffers.
+            if let Err(err) = nvim.nvim_paste(&text, true, -1).await {
+                warn!("failed to paste dropped text: {}", err);
+            }
+        });
+    }
+
+    fn show_context_menu(&self, x: f64, y: f64) {
+        let model = some_or_return!(self.context_menu(), "no context menu");
+
+        let popover = self
+            .imp()
+            .context_popover
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                let popover = gtk::PopoverMenu::from_model(None::<&gio::MenuModel>);
+                popover.set_has_arrow(false);
+                popover.set_halign(gtk::Align::Start);
+                popover.set_parent(self);
+                popover
+            })
+            .clone();
+
+        popover.set_menu_model(Some(&model));
+        popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+        popover.popup();
+    }
+
+    /// Update the GUI side selection. On release, the selected text is
+    /// copied to the clipboard.
+    fn update_selection(&self, action: Action, row: usize, col: usize) {
+        let imp = self.imp();
+
+        match action {
+            Action::Pressed => {
+                imp.selection.set(Some(Selection::new(row, col)));
+            }
+            Action::Drag => {
+                let mut sel = some_or_return!(imp.selection.get(), "drag without selection");
+                sel.end = (row, col);
+                imp.selection.set(Some(sel));
+            }
+            Action::Released => {
+                let sel = some_or_return!(imp.selection.get(), "release without selection");
+                if sel.start == sel.end {
+                    imp.selection.set(None);
+                } else {
+                    let text = self.selection_text(&sel);
+                    self.clipboard().set_text(&text);
+                    self.primary_clipboard().set_text(&text);
+                }
+            }
+            _ => {}
+        }
+
+        self.queue_draw();
+    }
+
+    fn selection_text(&self, sel: &Selection) -> String {
+        let rows = self.imp().buffer.get_rows();
+
+        sel.rows()
+            .filter_map(|i| {
+                let row = rows.get(i)?;
+                let cols = sel.cols(i, row.cells.len())?;
+                let text = row.cells[cols]
+                    .iter()
+                    .map(|cell| cell.text.as_str())
+                    .collect::<String>();
+
+                Some(text.trim_end().to_owned())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn put(&self, event: &GridLineRef<'_>) -> Result<(), DecodeError> {
+        self.imp().buffer.update_row(event)
     }
 
     pub fn resize(&self, event: GridResize) {
@@ -203,6 +414,7 @@ impl Grid {
     pub fn flush(&self, colors: &Colors) {
         let imp = self.imp();
         imp.buffer.flush(colors);
+        imp.fg.set(colors.fg);
 
         if imp.active.get() {
             // Update the text under the cursor, since in some cases neovim doesn't
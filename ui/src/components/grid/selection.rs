@@ -0,0 +1,68 @@
+use std::ops::Range;
+
+/// GUI side text selection, used when neovim has the mouse disabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Selection {
+    /// Where the selection was started, (row, col).
+    pub start: (usize, usize),
+    /// Where the selection currently ends, (row, col).
+    pub end: (usize, usize),
+}
+
+impl Selection {
+    pub fn new(row: usize, col: usize) -> Self {
+        Self {
+            start: (row, col),
+            end: (row, col),
+        }
+    }
+
+    /// Returns the start and end of the selection in order.
+    fn ordered(&self) -> ((usize, usize), (usize, usize)) {
+        if self.start <= self.end {
+            (self.start, self.end)
+        } else {
+            (self.end, self.start)
+        }
+    }
+
+    /// Rows covered by the selection.
+    pub fn rows(&self) -> Range<usize> {
+        let (start, end) = self.ordered();
+        start.0..end.0 + 1
+    }
+
+    /// Selected columns on `row`, for a grid with `width` columns.
+    pub fn cols(&self, row: usize, width: usize) -> Option<Range<usize>> {
+        let (start, end) = self.ordered();
+        if row < start.0 || row > end.0 {
+            return None;
+        }
+
+        let from = if row == start.0 { start.1 } else { 0 };
+        let to = if row == end.0 { end.1 + 1 } else { width };
+
+        Some(from.min(width)..to.min(width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Selection;
+
+    #[test]
+    fn test_selection_cols() {
+        let sel = Selection {
+            start: (3, 5),
+            end: (1, 2),
+        };
+
+        assert_eq!(sel.rows(), 1..4);
+        assert_eq!(sel.cols(0, 10), None);
+        assert_eq!(sel.cols(1, 10), Some(2..10));
+        assert_eq!(sel.cols(2, 10), Some(0..10));
+        assert_eq!(sel.cols(3, 10), Some(0..6));
+        assert_eq!(sel.cols(3, 4), Some(0..4));
+        assert_eq!(sel.cols(4, 10), None);
+    }
+}
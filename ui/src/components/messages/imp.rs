@@ -0,0 +1,51 @@
+use std::cell::RefCell;
+
+use gtk::{glib, glib::subclass::InitializingObject, subclass::prelude::*};
+use nvim::types::Object;
+
+/// A single message shown by `msg_show`.
+pub struct Message {
+    /// The message id, nil if neovim didn't provide one.
+    pub id: Object,
+    pub kind: String,
+    pub markup: String,
+    pub label: gtk::Label,
+}
+
+#[derive(gtk::CompositeTemplate, Default)]
+#[template(resource = "/com/github/vhakulinen/gnvim/messages.ui")]
+pub struct Messages {
+    #[template_child(id = "scrolled-window")]
+    pub scrolledwindow: TemplateChild<gtk::ScrolledWindow>,
+    #[template_child(id = "list")]
+    pub list: TemplateChild<gtk::Box>,
+
+    /// Currently visible messages, in the order they were shown.
+    pub messages: RefCell<Vec<Message>>,
+    /// The message history (i.e. `:messages`), if visible.
+    pub history: RefCell<Option<gtk::Label>>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for Messages {
+    const NAME: &'static str = "Messages";
+    type Type = super::Messages;
+    type ParentType = gtk::Widget;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.set_css_name("messages");
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for Messages {
+    fn dispose(&self) {
+        self.dispose_template();
+    }
+}
+
+impl WidgetImpl for Messages {}
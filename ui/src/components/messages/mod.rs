@@ -0,0 +1,200 @@
+use gtk::{glib, pango, prelude::*, subclass::prelude::*};
+use nvim::types::{
+    uievents::{MsgHistoryShow, MsgShow},
+    Object,
+};
+
+use crate::colors::Colors;
+
+mod imp;
+
+glib::wrapper! {
+    /// Native rendering of the `ext_messages` messages.
+    ///
+    /// Messages are stacked on top of each other. Errors and warnings stay
+    /// visible until the user dismisses them by clicking them, other messages
+    /// are removed on `msg_clear`.
+    pub struct Messages(ObjectSubclass<imp::Messages>)
+        @extends gtk::Widget,
+        @implements gtk::ConstraintTarget, gtk::Buildable, gtk::Accessible;
+}
+
+/// Message kinds that are kept visible until dismissed by the user.
+fn is_sticky(kind: &str) -> bool {
+    matches!(
+        kind,
+        "emsg" | "echoerr" | "lua_error" | "rpc_error" | "wmsg"
+    )
+}
+
+fn css_classes(kind: &str) -> Vec<&'static str> {
+    match kind {
+        "emsg" | "echoerr" | "lua_error" | "rpc_error" => vec!["message", "error"],
+        "wmsg" => vec!["message", "warning"],
+        _ => vec!["message"],
+    }
+}
+
+impl Messages {
+    fn new_label(&self, markup: &str) -> gtk::Label {
+        let label = gtk::Label::builder()
+            .use_markup(true)
+            .wrap(true)
+            .wrap_mode(pango::WrapMode::WordChar)
+            .xalign(0.0)
+            .label(markup)
+            .build();
+
+        // Clicking the message dismisses it.
+        let gesture = gtk::GestureClick::new();
+        gesture.connect_released(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            #[weak]
+            label,
+            move |_, _, _, _| {
+                obj.dismiss(&label);
+            }
+        ));
+        label.add_controller(gesture);
+
+        label
+    }
+
+    fn dismiss(&self, label: &gtk::Label) {
+        let imp = self.imp();
+
+        imp.messages.borrow_mut().retain(|msg| msg.label != *label);
+
+        let mut history = imp.history.borrow_mut();
+        if history.as_ref() == Some(label) {
+            history.take();
+        }
+        drop(history);
+
+        label.unparent();
+        self.update_visibility();
+    }
+
+    fn update_visibility(&self) {
+        let imp = self.imp();
+        self.set_visible(!imp.messages.borrow().is_empty() || imp.history.borrow().is_some());
+    }
+
+    pub fn show(&self, event: MsgShow, colors: &Colors) {
+        let imp = self.imp();
+
+        let markup = event
+            .content
+            .iter()
+            .map(|chunk| {
+                colors
+                    .get_hl(&chunk.attr_id)
+                    .pango_markup(&chunk.text_chunk)
+            })
+            .collect::<String>();
+
+        let mut messages = imp.messages.borrow_mut();
+
+        // Messages with an id replace the earlier message with the same id.
+        let existing = if event.id != Object::new(rmpv::Value::Nil) {
+            messages.iter().position(|msg| msg.id == event.id)
+        } else if event.replace_last || event.append {
+            messages.len().checked_sub(1)
+        } else {
+            None
+        };
+
+        match existing {
+            Some(index) => {
+                let msg = &mut messages[index];
+                if event.append {
+                    msg.markup.push_str(&markup);
+                } else {
+                    msg.markup = markup;
+                }
+
+                msg.kind = event.kind;
+                msg.label.set_markup(&msg.markup);
+                msg.label.set_css_classes(&css_classes(&msg.kind));
+            }
+            None if event
+                .content
+                .iter()
+                .all(|chunk| chunk.text_chunk.is_empty()) => {}
+            None => {
+                let label = self.new_label(&markup);
+                label.set_css_classes(&css_classes(&event.kind));
+                imp.list.append(&label);
+
+                messages.push(imp::Message {
+                    id: event.id,
+                    kind: event.kind,
+                    markup,
+                    label,
+                });
+            }
+        }
+
+        drop(messages);
+        self.update_visibility();
+    }
+
+    pub fn clear(&self) {
+        let imp = self.imp();
+
+        imp.messages.borrow_mut().retain(|msg| {
+            if is_sticky(&msg.kind) {
+                true
+            } else {
+                msg.label.unparent();
+                false
+            }
+        });
+
+        self.update_visibility();
+    }
+
+    pub fn history_show(&self, event: MsgHistoryShow, colors: &Colors) {
+        let imp = self.imp();
+
+        if let Some(prev) = imp.history.borrow_mut().take() {
+            prev.unparent();
+        }
+
+        if event.entries.is_empty() {
+            self.update_visibility();
+            return;
+        }
+
+        let markup = event
+            .entries
+            .iter()
+            .map(|entry| {
+                entry
+                    .content
+                    .iter()
+                    .map(|chunk| {
+                        colors
+                            .get_hl(&chunk.attr_id)
+                            .pango_markup(&chunk.text_chunk)
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let label = self.new_label(&markup);
+        label.set_css_classes(&["message", "history"]);
+        imp.list.append(&label);
+        imp.history.replace(Some(label));
+
+        self.update_visibility();
+    }
+}
+
+impl Default for Messages {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
@@ -7,7 +7,10 @@ pub mod grid;
 #[path = "./grid-buffer/mod.rs"]
 pub mod grid_buffer;
 pub mod maxsize;
+pub mod messages;
+pub mod msgstrip;
 pub mod msgwin;
+pub mod picker;
 pub mod popupmenu;
 pub mod shell;
 pub mod tabline;
@@ -19,7 +22,10 @@ pub use fixedz::Fixedz;
 pub use grid::Grid;
 pub use grid_buffer::GridBuffer;
 pub use maxsize::MaxSizeLayoutManager;
+pub use messages::Messages;
+pub use msgstrip::MsgStrip;
 pub use msgwin::MsgWin;
+pub use picker::SessionPicker;
 pub use popupmenu::Popupmenu;
 pub use shell::Shell;
 pub use tabline::Tabline;
@@ -0,0 +1,36 @@
+use gtk::{glib, glib::subclass::InitializingObject, subclass::prelude::*};
+
+#[derive(gtk::CompositeTemplate, Default)]
+#[template(resource = "/com/github/vhakulinen/gnvim/msgstrip.ui")]
+pub struct MsgStrip {
+    #[template_child(id = "showmode")]
+    pub showmode: TemplateChild<gtk::Label>,
+    #[template_child(id = "showcmd")]
+    pub showcmd: TemplateChild<gtk::Label>,
+    #[template_child(id = "ruler")]
+    pub ruler: TemplateChild<gtk::Label>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for MsgStrip {
+    const NAME: &'static str = "MsgStrip";
+    type Type = super::MsgStrip;
+    type ParentType = gtk::Widget;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.set_css_name("msgstrip");
+        klass.bind_template();
+    }
+
+    fn instance_init(obj: &InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+impl ObjectImpl for MsgStrip {
+    fn dispose(&self) {
+        self.dispose_template();
+    }
+}
+
+impl WidgetImpl for MsgStrip {}
@@ -0,0 +1,54 @@
+use gtk::{glib, subclass::prelude::*};
+use nvim::types::{
+    uievents::{MsgRuler, MsgShowcmd, MsgShowmode},
+    MsgShowContent,
+};
+
+use crate::colors::Colors;
+
+mod imp;
+
+glib::wrapper! {
+    /// Persistent strip for the `msg_showmode`, `msg_showcmd` and `msg_ruler`
+    /// contents.
+    pub struct MsgStrip(ObjectSubclass<imp::MsgStrip>)
+        @extends gtk::Widget,
+        @implements gtk::ConstraintTarget, gtk::Buildable, gtk::Accessible;
+}
+
+fn content_markup(content: &[MsgShowContent], colors: &Colors) -> String {
+    content
+        .iter()
+        .map(|chunk| {
+            colors
+                .get_hl(&chunk.attr_id)
+                .pango_markup(&chunk.text_chunk)
+        })
+        .collect::<String>()
+}
+
+impl MsgStrip {
+    pub fn showmode(&self, event: MsgShowmode, colors: &Colors) {
+        self.imp()
+            .showmode
+            .set_markup(&content_markup(&event.content, colors));
+    }
+
+    pub fn showcmd(&self, event: MsgShowcmd, colors: &Colors) {
+        self.imp()
+            .showcmd
+            .set_markup(&content_markup(&event.content, colors));
+    }
+
+    pub fn ruler(&self, event: MsgRuler, colors: &Colors) {
+        self.imp()
+            .ruler
+            .set_markup(&content_markup(&event.content, colors));
+    }
+}
+
+impl Default for MsgStrip {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
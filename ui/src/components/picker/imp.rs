@@ -0,0 +1,60 @@
+use std::sync::OnceLock;
+
+use adw::subclass::prelude::*;
+use gtk::{
+    glib::{self, subclass::Signal},
+    prelude::*,
+};
+
+#[derive(gtk::CompositeTemplate, Default)]
+#[template(resource = "/com/github/vhakulinen/gnvim/picker.ui")]
+pub struct SessionPicker {
+    #[template_child(id = "servers")]
+    pub servers: TemplateChild<adw::PreferencesGroup>,
+    #[template_child(id = "spinner")]
+    pub spinner: TemplateChild<gtk::Spinner>,
+}
+
+#[glib::object_subclass]
+impl ObjectSubclass for SessionPicker {
+    const NAME: &'static str = "SessionPicker";
+    type Type = super::SessionPicker;
+    type ParentType = adw::ApplicationWindow;
+
+    fn class_init(klass: &mut Self::Class) {
+        klass.bind_template();
+        klass.bind_template_callbacks();
+    }
+
+    fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+        obj.init_template();
+    }
+}
+
+#[gtk::template_callbacks]
+impl SessionPicker {
+    #[template_callback]
+    fn new_instance(&self) {
+        self.obj().emit_by_name::<()>("picked", &[&None::<String>]);
+    }
+}
+
+impl ObjectImpl for SessionPicker {
+    fn signals() -> &'static [Signal] {
+        static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+        SIGNALS.get_or_init(|| {
+            // The address of the picked server, or none for a new instance.
+            vec![Signal::builder("picked")
+                .param_types([Option::<String>::static_type()])
+                .build()]
+        })
+    }
+}
+
+impl WidgetImpl for SessionPicker {}
+
+impl WindowImpl for SessionPicker {}
+
+impl ApplicationWindowImpl for SessionPicker {}
+
+impl AdwApplicationWindowImpl for SessionPicker {}
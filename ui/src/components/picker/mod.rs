@@ -0,0 +1,188 @@
+mod imp;
+
+use std::{os::unix::fs::FileTypeExt, path::PathBuf, time::Duration};
+
+use adw::prelude::*;
+use futures::future;
+use gtk::{gio, glib, subclass::prelude::*};
+use nvim::{
+    rpc::{message::Message, Caller, RpcReader},
+    NeovimApi,
+};
+
+use crate::{debug, nvim::Neovim, spawn_local};
+
+/// How long to wait for a server to answer before leaving it out.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+glib::wrapper! {
+    pub struct SessionPicker(ObjectSubclass<imp::SessionPicker>)
+        @extends adw::ApplicationWindow, gtk::ApplicationWindow, gtk::Window, gtk::Widget,
+        @implements gio::ActionGroup, gio::ActionMap, gtk::Accessible, gtk::Buildable,
+                    gtk::ConstraintTarget, gtk::Native, gtk::Root, gtk::ShortcutManager;
+}
+
+/// A running neovim server.
+#[derive(Debug)]
+struct Server {
+    addr: String,
+    cwd: String,
+    /// Name of the current buffer.
+    file: String,
+}
+
+/// Sockets that look like neovim servers, i.e. the default `serverstart()`
+/// addresses in `stdpath('run')`.
+pub fn server_sockets() -> Vec<PathBuf> {
+    // `stdpath('run')` is $XDG_RUNTIME_DIR, or a random directory under
+    // $TMPDIR/nvim.$USER when that's not set.
+    let mut dirs = vec![glib::user_runtime_dir()];
+    let tmp = glib::tmp_dir().join(format!("nvim.{}", glib::user_name().to_string_lossy()));
+    if let Ok(entries) = std::fs::read_dir(tmp) {
+        dirs.extend(
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir()),
+        );
+    }
+
+    dirs.iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_name().to_string_lossy().starts_with("nvim.")
+                && entry.file_type().is_ok_and(|t| t.is_socket())
+        })
+        .map(|entry| entry.path())
+        .collect()
+}
+
+/// Connects to `addr`, and asks for its cwd and current file.
+async fn probe(addr: String) -> Option<Server> {
+    let nvim = Neovim::default();
+    let reader = match nvim.connect(&addr).await {
+        Ok(reader) => reader,
+        Err(err) => {
+            debug!("failed to connect to {}: {}", addr, err);
+            return None;
+        }
+    };
+
+    // Pass the responses back until we disconnect.
+    let weak = nvim.downgrade();
+    spawn_local!(async move {
+        let mut reader: RpcReader<_> = reader.into();
+        while let Ok(msg) = reader.recv().await {
+            let Some(nvim) = weak.upgrade() else {
+                break;
+            };
+
+            if let Message::Response(res) = msg {
+                let _ = nvim.handle_response(res);
+            }
+        }
+    });
+
+    let info = async {
+        nvim.nvim_get_api_info().await.ok()?;
+        let (cwd, file): (String, String) = nvim
+            .call(
+                "nvim_exec_lua",
+                (
+                    "return { vim.fn.getcwd(), vim.api.nvim_buf_get_name(0) }",
+                    Vec::<rmpv::Value>::new(),
+                ),
+            )
+            .await
+            .ok()?;
+
+        Some(Server {
+            addr: addr.clone(),
+            cwd,
+            file,
+        })
+    };
+
+    let server = match future::select(Box::pin(info), glib::timeout_future(PROBE_TIMEOUT)).await {
+        future::Either::Left((server, _)) => server,
+        future::Either::Right(_) => {
+            debug!("probing {} timed out", addr);
+            None
+        }
+    };
+
+    nvim.disconnect();
+    server
+}
+
+fn home_relative(path: &str) -> String {
+    let home = glib::home_dir();
+    match std::path::Path::new(path).strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => String::from("~"),
+        Ok(rest) => format!("~/{}", rest.to_string_lossy()),
+        Err(_) => path.to_owned(),
+    }
+}
+
+impl SessionPicker {
+    pub fn new(app: &impl IsA<adw::Application>) -> Self {
+        glib::Object::builder().property("application", app).build()
+    }
+
+    /// Looks for running servers, and lists the ones that respond.
+    pub fn scan(&self) {
+        spawn_local!(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            async move {
+                let servers = future::join_all(
+                    server_sockets()
+                        .into_iter()
+                        .map(|path| probe(path.to_string_lossy().into_owned())),
+                )
+                .await;
+
+                obj.show_servers(servers.into_iter().flatten().collect());
+            }
+        ));
+    }
+
+    fn show_servers(&self, servers: Vec<Server>) {
+        let imp = self.imp();
+        imp.spinner.set_spinning(false);
+        imp.spinner.set_visible(false);
+        imp.servers.set_description(if servers.is_empty() {
+            Some("No running servers found")
+        } else {
+            None
+        });
+
+        for server in servers {
+            let file = if server.file.is_empty() {
+                String::from("[No Name]")
+            } else {
+                home_relative(&server.file)
+            };
+
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&home_relative(&server.cwd)).as_str())
+                .subtitle(glib::markup_escape_text(&file).as_str())
+                .tooltip_text(server.addr.as_str())
+                .activatable(true)
+                .build();
+            row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+            row.connect_activated(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| {
+                    obj.emit_by_name::<()>("picked", &[&Some(server.addr.clone())]);
+                }
+            ));
+
+            imp.servers.add(&row);
+        }
+    }
+}
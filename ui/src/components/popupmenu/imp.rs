@@ -103,6 +103,12 @@ impl ObjectImpl for Popupmenu {
                     .chain_property::<PopupmenuObject>("word")
                     .bind(&item, "word", Some(listitem));
 
+                // Menu value.
+                listitem
+                    .property_expression("item")
+                    .chain_property::<PopupmenuObject>("menu")
+                    .bind(&item, "menu", Some(listitem));
+
                 listitem.set_child(Some(&item));
             }
         ));
@@ -14,7 +14,7 @@ pub use object::PopupmenuObject;
 use nvim::types::PopupmenuItem;
 use row::Row;
 
-use crate::{colors::Colors, nvim::Neovim, spawn_local, SCALE};
+use crate::{colors::Colors, nvim::Neovim, spawn_local, warn, SCALE};
 
 glib::wrapper! {
     pub struct Popupmenu(ObjectSubclass<imp::Popupmenu>)
@@ -47,6 +47,16 @@ impl Popupmenu {
         self.imp().listview.preferred_size()
     }
 
+    /// Returns the `info` text of the currently selected item. Empty if
+    /// nothing is selected.
+    pub fn selected_info(&self) -> String {
+        self.imp()
+            .store
+            .selected_object()
+            .map(|item| item.info())
+            .unwrap_or_default()
+    }
+
     pub fn select(&self, n: i64) {
         let imp = self.imp();
 
@@ -76,10 +86,9 @@ impl Popupmenu {
             #[weak]
             nvim,
             async move {
-                nvim.clone()
-                    .nvim_ui_pum_set_bounds(w, h, row, col)
-                    .await
-                    .expect("nvim_ui_pum_set_bounds failed");
+                if let Err(err) = nvim.clone().nvim_ui_pum_set_bounds(w, h, row, col).await {
+                    warn!("nvim_ui_pum_set_bounds failed: {}", err);
+                }
             }
         ));
     }
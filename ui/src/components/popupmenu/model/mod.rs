@@ -46,6 +46,20 @@ impl Model {
         }
     }
 
+    /// Returns the currently selected item, if any. Also considers items
+    /// that are not yet lazily added to the model.
+    pub fn selected_object(&self) -> Option<PopupmenuObject> {
+        let imp = self.imp();
+        let i = imp.selected_item.get()? as usize;
+
+        let items = imp.items.borrow();
+        if let Some(item) = items.get(i) {
+            return Some(item.clone());
+        }
+
+        imp.to_add.borrow().get(i - items.len()).cloned()
+    }
+
     pub fn set_items(&self, items: Vec<PopupmenuObject>) {
         let imp = self.imp();
 
@@ -16,6 +16,8 @@ impl PopupmenuObject {
         glib::Object::builder()
             .property("word", &item.word)
             .property("kind", kind)
+            .property("menu", &item.menu)
+            .property("info", &item.info)
             .build()
     }
 }
@@ -40,6 +42,10 @@ mod imp {
         kind: RefCell<Kind>,
         #[property(get, set)]
         word: RefCell<String>,
+        #[property(get, set)]
+        menu: RefCell<String>,
+        #[property(get, set)]
+        info: RefCell<String>,
     }
 
     #[glib::object_subclass]
@@ -18,6 +18,9 @@ pub struct Row {
     #[property(name = "kind", set = Self::set_kind, type = String)]
     #[template_child(id = "kind")]
     pub kind: TemplateChild<gtk::Label>,
+    #[property(name = "menu", set = Self::set_menu, type = String)]
+    #[template_child(id = "menu")]
+    pub menu: TemplateChild<gtk::Label>,
 
     #[property(set = Self::set_font)]
     pub font: RefCell<Font>,
@@ -35,6 +38,11 @@ impl Row {
         self.kind.set_label(&v);
     }
 
+    fn set_menu(&self, v: String) {
+        self.menu.set_visible(!v.is_empty());
+        self.menu.set_label(&v);
+    }
+
     fn set_font(&self, font: Font) {
         let w = (font.char_width() / SCALE).ceil() as i32;
 
@@ -2,7 +2,7 @@ use std::cell::{Cell, RefCell};
 
 use gtk::glib::subclass::InitializingObject;
 use gtk::subclass::prelude::*;
-use gtk::{glib, gsk};
+use gtk::{gio, glib, gsk};
 use gtk::{graphene, prelude::*};
 
 use crate::boxed::ModeInfo;
@@ -20,6 +20,18 @@ pub struct PopupmenuPos {
     grid: i64,
 }
 
+/// The area the popupmenu was placed in by `adjust_pmenu`.
+#[derive(Default, Clone, Copy)]
+pub struct PopupmenuRect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    /// The available area's size.
+    max_width: f32,
+    max_height: f32,
+}
+
 #[derive(gtk::CompositeTemplate, glib::Properties, Default)]
 #[properties(wrapper_type = super::Shell)]
 #[template(resource = "/com/github/vhakulinen/gnvim/shell.ui")]
@@ -39,6 +51,11 @@ pub struct Shell {
 
     #[template_child(id = "popupmenu")]
     pub popupmenu: TemplateChild<Popupmenu>,
+    /// Documentation panel for the selected popupmenu item.
+    #[template_child(id = "popupmenu-info")]
+    pub popupmenu_info: TemplateChild<gtk::ScrolledWindow>,
+    #[template_child(id = "popupmenu-info-label")]
+    pub popupmenu_info_label: TemplateChild<gtk::Label>,
 
     #[property(get, set)]
     pub nvim: RefCell<Neovim>,
@@ -53,6 +70,9 @@ pub struct Shell {
     pub font: RefCell<Font>,
     #[property(get, set, default = false)]
     pub busy: Cell<bool>,
+    /// If any of our windows has the focus.
+    #[property(get, set, default = false)]
+    pub focused: Cell<bool>,
     #[property(get, set)]
     pub current_mode_info: RefCell<ModeInfo>,
     #[property(get, set, minimum = 0.0)]
@@ -61,6 +81,12 @@ pub struct Shell {
     pub cursor_position_transition: Cell<f64>,
     #[property(get, set, minimum = 0.0)]
     pub scroll_transition: Cell<f64>,
+    /// If neovim has the mouse enabled.
+    #[property(get, set, default = true)]
+    pub mouse_enabled: Cell<bool>,
+    /// Context menu for the grids.
+    #[property(get, set, nullable)]
+    pub context_menu: RefCell<Option<gio::MenuModel>>,
     /// Source id for debouncing nvim resizing.
     pub resize_id: RefCell<Option<glib::SourceId>>,
     /// Our previous size. Used to track when we need to tell neovim to resize
@@ -72,6 +98,7 @@ pub struct Shell {
     #[property(name = "pmenu-visible", member = visible, get, set, type = bool)]
     #[property(name = "pmenu-grid", member = grid, get, set, type = i64)]
     pub pmenu_pos: RefCell<PopupmenuPos>,
+    pub pmenu_rect: Cell<PopupmenuRect>,
 }
 
 impl Shell {
@@ -80,6 +107,7 @@ impl Shell {
         let pmenu_pos = self.pmenu_pos.borrow();
 
         if !pmenu_pos.visible {
+            self.popupmenu_info.set_visible(false);
             return;
         }
 
@@ -110,7 +138,7 @@ impl Shell {
         // TODO(ville): Would be nice to make the popupmenu to retain its
         // placement (e.g. above vs. below) when the popupmenu is already
         // shown and displayed in a way where it has enough space.
-        let (y, max_h) = if pmenu_h > below && above > below {
+        let (y, pmenu_max_h) = if pmenu_h > below && above > below {
             // Place above.
             ((y - font.height() / SCALE - pmenu_h).max(0.0), above)
         } else {
@@ -126,10 +154,55 @@ impl Shell {
         };
 
         self.popupmenu.set_max_width(max_w.floor() as i32);
-        self.popupmenu.set_max_height(max_h.floor() as i32);
+        self.popupmenu.set_max_height(pmenu_max_h.floor() as i32);
         self.fixed.move_(&*self.popupmenu, x, y);
 
         self.popupmenu.report_pum_bounds(&self.nvim.borrow(), x, y);
+
+        self.pmenu_rect.set(PopupmenuRect {
+            x,
+            y,
+            width: pmenu_w.min(max_w),
+            height: pmenu_h.min(pmenu_max_h),
+            max_width: max_w,
+            max_height: max_h,
+        });
+        self.adjust_pmenu_info();
+    }
+
+    /// Show (or hide) the selected popupmenu item's info next to the
+    /// popupmenu.
+    fn adjust_pmenu_info(&self) {
+        let info = self.popupmenu.selected_info();
+        let rect = self.pmenu_rect.get();
+
+        // Place the info on the side that has more room.
+        let right = rect.max_width - rect.x - rect.width;
+        let left = rect.x;
+        let avail = right.max(left);
+
+        let min_w = self.font.borrow().char_width() / SCALE * 10.0;
+        if info.is_empty() || avail < min_w {
+            self.popupmenu_info.set_visible(false);
+            return;
+        }
+
+        self.popupmenu_info_label.set_text(&info);
+        self.popupmenu_info
+            .set_max_content_width(avail.floor() as i32);
+        self.popupmenu_info
+            .set_max_content_height((rect.max_height - rect.y).max(rect.height).floor() as i32);
+        self.popupmenu_info.set_visible(true);
+
+        let (_, req) = self.popupmenu_info.preferred_size();
+        let w = (req.width() as f32).min(avail);
+        let x = if right >= left {
+            rect.x + rect.width
+        } else {
+            rect.x - w
+        };
+
+        self.fixed.move_(&*self.popupmenu_info, x, rect.y);
     }
 }
 
@@ -165,6 +238,8 @@ impl ObjectImpl for Shell {
         self.grids.borrow_mut().push(self.root_grid.clone());
 
         let obj = self.obj();
+        obj.set_mouse_enabled(true);
+
         self.popupmenu.store().connect_items_changed(glib::clone!(
             #[weak]
             obj,
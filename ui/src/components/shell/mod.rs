@@ -1,10 +1,11 @@
 use std::time::Duration;
 
-use gtk::{glib, graphene, gsk, prelude::*, subclass::prelude::*};
+use gtk::{gdk, glib, graphene, gsk, prelude::*, subclass::prelude::*};
+use nvim::rpc::{redraw::DecodeError, GridLineRef};
 use nvim::types::uievents::{
-    GridClear, GridCursorGoto, GridDestroy, GridLine, GridResize, GridScroll, MsgSetPos,
-    PopupmenuSelect, PopupmenuShow, WinClose, WinExternalPos, WinFloatPos, WinHide, WinPos,
-    WinViewport, WinViewportMargins,
+    GridClear, GridCursorGoto, GridDestroy, GridResize, GridScroll, MsgSetPos, PopupmenuSelect,
+    PopupmenuShow, WinClose, WinExternalPos, WinFloatPos, WinHide, WinPos, WinViewport,
+    WinViewportMargins,
 };
 use nvim::NeovimApi;
 
@@ -53,6 +54,9 @@ impl Shell {
             self.bind_property("busy", &grid, "busy")
                 .flags(glib::BindingFlags::SYNC_CREATE)
                 .build();
+            self.bind_property("focused", &grid, "focused")
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
             self.bind_property("current-mode-info", &grid, "mode-info")
                 .flags(glib::BindingFlags::SYNC_CREATE)
                 .build();
@@ -69,6 +73,12 @@ impl Shell {
             self.bind_property("scroll-transition", &grid, "scroll-transition")
                 .flags(glib::BindingFlags::SYNC_CREATE)
                 .build();
+            self.bind_property("mouse-enabled", &grid, "mouse-enabled")
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
+            self.bind_property("context-menu", &grid, "context-menu")
+                .flags(glib::BindingFlags::SYNC_CREATE)
+                .build();
 
             self.imp().grids.borrow_mut().push(grid.clone());
             grid
@@ -94,10 +104,13 @@ impl Shell {
                         #[weak]
                         obj,
                         async move {
-                            obj.nvim()
+                            if let Err(err) = obj
+                                .nvim()
                                 .nvim_ui_try_resize_grid(1, cols.max(1) as i64, rows.max(1) as i64)
                                 .await
-                                .expect("nvim_ui_try_resize failed");
+                            {
+                                warn!("nvim_ui_try_resize failed: {}", err);
+                            }
                         }
                     ));
 
@@ -116,6 +129,54 @@ impl Shell {
         }
     }
 
+    /// Flash the current grid.
+    pub fn visual_bell(&self, color: gdk::RGBA, duration: Duration) {
+        self.imp()
+            .current_grid
+            .borrow()
+            .visual_bell(color, duration);
+    }
+
+    /// Updates the focus from our windows (the main window, and the
+    /// external windows), and tells neovim about it. Deferred to idle, so
+    /// that moving the focus between our windows doesn't look like losing
+    /// it.
+    pub fn update_focus(&self) {
+        glib::idle_add_local_once(glib::clone!(
+            #[weak(rename_to = obj)]
+            self,
+            move || {
+                let focused = obj
+                    .root()
+                    .and_downcast::<gtk::Window>()
+                    .is_some_and(|win| win.is_active())
+                    || obj
+                        .imp()
+                        .grids
+                        .borrow()
+                        .iter()
+                        .any(|grid| grid.external_window().is_some_and(|win| win.is_active()));
+
+                if focused == obj.focused() {
+                    return;
+                }
+
+                obj.set_focused(focused);
+                spawn_local!(glib::clone!(
+                    #[weak]
+                    obj,
+                    async move {
+                        // Fails if we're not attached yet, in which case
+                        // the focus is sent once we are.
+                        if let Err(err) = obj.nvim().nvim_ui_set_focus(focused).await {
+                            warn!("nvim_ui_set_focus failed: {}", err);
+                        }
+                    }
+                ));
+            }
+        ));
+    }
+
     pub fn busy_start(&self) {
         self.set_busy(true);
     }
@@ -124,8 +185,8 @@ impl Shell {
         self.set_busy(false);
     }
 
-    pub fn handle_grid_line(&self, event: GridLine) {
-        self.find_or_create_grid(event.grid).put(event);
+    pub fn handle_grid_line(&self, event: &GridLineRef<'_>) -> Result<(), DecodeError> {
+        self.find_or_create_grid(event.grid).put(event)
     }
 
     pub fn handle_grid_resize(&self, event: GridResize) {
@@ -305,7 +366,13 @@ impl Shell {
 
         let grid = self.find_or_create_grid(event.grid);
         grid.set_nvim_window(Some(event.win));
-        grid.make_external(parent);
+        if let Some(win) = grid.make_external(parent) {
+            win.connect_is_active_notify(glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                move |_| obj.update_focus()
+            ));
+        }
     }
 
     pub fn handle_win_viewport(&self, event: WinViewport) {
@@ -4,7 +4,7 @@ use glib::subclass::InitializingObject;
 use gtk::{glib, prelude::*, subclass::prelude::*};
 use nvim::NeovimApi;
 
-use crate::{boxed::Tabpage, nvim::Neovim, spawn_local};
+use crate::{boxed::Tabpage, nvim::Neovim, spawn_local, warn};
 
 #[derive(Default, gtk::CompositeTemplate)]
 #[template(resource = "/com/github/vhakulinen/gnvim/tab.ui")]
@@ -49,9 +49,9 @@ impl ObjectImpl for Tab {
                 spawn_local!(async move {
                     let nvim = obj.nvim();
                     let page = obj.imp().tabpage.borrow().clone().expect("tabpage not set");
-                    nvim.nvim_set_current_tabpage(&page)
-                        .await
-                        .expect("nvim_set_current_tabpage failed");
+                    if let Err(err) = nvim.nvim_set_current_tabpage(&page).await {
+                        warn!("nvim_set_current_tabpage failed: {}", err);
+                    }
                 });
             }
         ));
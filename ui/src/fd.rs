@@ -1,3 +1,7 @@
+use std::os::fd::{FromRawFd, OwnedFd};
+
+use gtk::{gio, glib, prelude::*};
+
 pub fn dup_stdin() -> Option<i32> {
     cfg_if::cfg_if! {
         if #[cfg(unix)] {
@@ -33,3 +37,90 @@ pub fn dup_stdin() -> Option<i32> {
         }
     }
 }
+
+/// D-Bus interface for passing piped stdin to the primary instance.
+const STDIN_INTERFACE: &str = "com.github.vhakulinen.gnvim.Stdin";
+const STDIN_XML: &str = r#"
+<node>
+  <interface name="com.github.vhakulinen.gnvim.Stdin">
+    <method name="Open">
+      <arg type="h" name="fd" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// Sends our stdin to the primary instance of `app`, see `register_stdin`.
+pub fn send_stdin(app: &gio::Application) -> Result<(), glib::Error> {
+    let (Some(conn), Some(path), Some(name)) = (
+        app.dbus_connection(),
+        app.dbus_object_path(),
+        app.application_id(),
+    ) else {
+        return Err(glib::Error::new(
+            gio::IOErrorEnum::NotConnected,
+            "not registered on D-Bus",
+        ));
+    };
+
+    let fds = gio::UnixFDList::new();
+    let handle = fds.append(std::io::stdin())?;
+
+    conn.call_with_unix_fd_list_sync(
+        Some(&name),
+        &path,
+        STDIN_INTERFACE,
+        "Open",
+        Some(&(glib::variant::Handle(handle),).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        -1,
+        Some(&fds),
+        gio::Cancellable::NONE,
+    )?;
+
+    Ok(())
+}
+
+/// Accepts stdin from other instances (see `send_stdin`), passing it to `f`.
+/// Must be called in the primary instance, once registered.
+pub fn register_stdin<F: Fn(gio::InputStream) + 'static>(
+    app: &gio::Application,
+    f: F,
+) -> Result<(), glib::Error> {
+    let (Some(conn), Some(path)) = (app.dbus_connection(), app.dbus_object_path()) else {
+        return Err(glib::Error::new(
+            gio::IOErrorEnum::NotConnected,
+            "not registered on D-Bus",
+        ));
+    };
+
+    let iface = gio::DBusNodeInfo::for_xml(STDIN_XML)?
+        .lookup_interface(STDIN_INTERFACE)
+        .expect("stdin interface");
+
+    conn.register_object(&path, &iface)
+        .method_call(move |_, _, _, _, _, params, invocation| {
+            // "Open" is the only method.
+            let stream = params
+                .get::<(glib::variant::Handle,)>()
+                .zip(invocation.message().unix_fd_list())
+                .ok_or_else(|| {
+                    glib::Error::new(gio::IOErrorEnum::InvalidArgument, "missing stdin fd")
+                })
+                .and_then(|((handle,), fds)| fds.get(handle.0))
+                // SAFETY: The fd is a duplicate that we own.
+                .map(|fd| unsafe { gio::UnixInputStream::take_fd(OwnedFd::from_raw_fd(fd)) });
+
+            match stream {
+                Ok(stream) => {
+                    f(stream.upcast());
+                    invocation.return_value(None);
+                }
+                Err(err) => invocation.return_gerror(err),
+            }
+        })
+        .build()?;
+
+    Ok(())
+}
@@ -12,6 +12,7 @@ mod font;
 mod input;
 mod macros;
 mod math;
+mod menu;
 mod nvim;
 mod render;
 
@@ -24,5 +25,12 @@ fn main() -> ExitCode {
     gio::resources_register_include!("gnvim.gresource").expect("Failed to register resources.");
 
     let app = app::App::default();
-    app.run()
+    let code = app.run();
+
+    // The exit status of a local command line is ignored once the main loop
+    // has run, so `--wait` in the primary instance needs to pass it here.
+    match app.wait_status() {
+        0 => code,
+        status => ExitCode::from(status),
+    }
 }
@@ -0,0 +1,123 @@
+use gtk::{gio, prelude::*};
+use nvim::serde;
+
+/// Name of the neovim's popup (e.g. right click) menu.
+const POPUP: &str = "PopUp";
+
+/// Menu from neovim's `menu_get()`.
+#[derive(Debug, serde::Deserialize)]
+#[serde(crate = "nvim::serde")]
+pub struct Menu {
+    pub name: String,
+    #[serde(default)]
+    pub hidden: i64,
+    #[serde(default)]
+    pub submenus: Vec<Menu>,
+}
+
+impl Menu {
+    fn is_separator(&self) -> bool {
+        self.name.len() > 1 && self.name.starts_with('-') && self.name.ends_with('-')
+    }
+
+    fn is_visible(&self) -> bool {
+        // Menus starting with "]" are hidden, see `:h hidden-menus`.
+        self.hidden == 0 && !self.name.starts_with(']')
+    }
+}
+
+/// Escapes menu name for `:emenu`.
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\")
+        .replace('.', "\\.")
+        .replace(' ', "\\ ")
+}
+
+/// Escapes menu name for gtk's menu label, so that underscores won't be
+/// used as mnemonics.
+fn label(name: &str) -> String {
+    name.replace('_', "__")
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        escape(name)
+    } else {
+        format!("{}.{}", prefix, escape(name))
+    }
+}
+
+/// Appends `menus` to `parent`. Each item activates `action` with its
+/// `:emenu` path.
+fn append(parent: &gio::Menu, menus: &[Menu], action: &str, prefix: &str) {
+    let mut section = gio::Menu::new();
+
+    for menu in menus.iter().filter(|menu| menu.is_visible()) {
+        if menu.is_separator() {
+            if section.n_items() > 0 {
+                parent.append_section(None, &section);
+                section = gio::Menu::new();
+            }
+            continue;
+        }
+
+        let path = join(prefix, &menu.name);
+        if menu.submenus.is_empty() {
+            let item = gio::MenuItem::new(Some(&label(&menu.name)), None);
+            item.set_action_and_target_value(Some(action), Some(&path.to_variant()));
+            section.append_item(&item);
+        } else {
+            let submenu = gio::Menu::new();
+            append(&submenu, &menu.submenus, action, &path);
+            section.append_submenu(Some(&label(&menu.name)), &submenu);
+        }
+    }
+
+    if section.n_items() > 0 {
+        parent.append_section(None, &section);
+    }
+}
+
+/// Creates the menubar model. The popup menu is excluded.
+pub fn menubar(menus: &[Menu], action: &str) -> gio::Menu {
+    let menubar = gio::Menu::new();
+
+    for menu in menus
+        .iter()
+        .filter(|menu| menu.is_visible() && !menu.submenus.is_empty() && menu.name != POPUP)
+    {
+        let submenu = gio::Menu::new();
+        append(&submenu, &menu.submenus, action, &escape(&menu.name));
+        menubar.append_submenu(Some(&label(&menu.name)), &submenu);
+    }
+
+    menubar
+}
+
+/// Creates the popup menu model, if neovim has one.
+pub fn popup(menus: &[Menu], action: &str) -> Option<gio::Menu> {
+    let menu = menus.iter().find(|menu| menu.name == POPUP)?;
+
+    let popup = gio::Menu::new();
+    append(&popup, &menu.submenus, action, POPUP);
+
+    Some(popup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, join};
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Save As"), "Save\\ As");
+        assert_eq!(escape("1.5x"), "1\\.5x");
+        assert_eq!(escape("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn test_join() {
+        assert_eq!(join("", "File"), "File");
+        assert_eq!(join("File", "Save As"), "File.Save\\ As");
+    }
+}
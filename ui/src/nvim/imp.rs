@@ -1,28 +1,40 @@
-use std::cell::RefCell;
+use std::{cell::RefCell, rc::Rc};
+use std::{fs::File, io::BufWriter};
 
-use futures::lock::Mutex;
+use futures::{channel::oneshot, lock::Mutex};
 use gtk::{
     gio::{self},
     glib,
     subclass::prelude::*,
 };
-use nvim::rpc::caller::Sender;
+use nvim::rpc::{caller::Callbacks, record::Recorder};
 
 pub enum Connection {
     Subprocess(gio::Subprocess),
     Socket(gio::SocketConnection),
 }
 
+/// Stderr of a subprocess, captured as it's read.
+pub struct Stderr {
+    pub output: Rc<RefCell<Vec<u8>>>,
+    /// Resolves once the subprocess has closed its stderr.
+    pub closed: oneshot::Receiver<()>,
+}
+
 #[derive(Default)]
 pub struct Neovim {
     pub writer: Mutex<Option<gio::OutputStreamAsyncWrite<gio::PollableOutputStream>>>,
     pub msgid_counter: RefCell<u32>,
-    pub callbacks: RefCell<Vec<(u32, Sender)>>,
+    pub callbacks: RefCell<Callbacks>,
     /// Store reference to the actual "connection" to nvim (i.e. subprocess or socket).
     ///
     /// We need this because dropping the `gio::SocketConnection` will close the actual
     /// connection, and keeping reference to the I/O channels wont be enough to avoid this.
     pub connection: RefCell<Option<Connection>>,
+    /// Captured stderr of the subprocess, if any.
+    pub stderr: RefCell<Option<Stderr>>,
+    /// Session log, if we're recording.
+    pub recorder: RefCell<Option<Recorder<BufWriter<File>>>>,
 }
 
 #[glib::object_subclass]
@@ -1,14 +1,64 @@
-use std::ffi::OsStr;
+use std::{cell::RefCell, ffi::OsStr, fs::File, io::BufWriter, rc::Rc};
 
-use futures::channel::oneshot;
+use futures::{channel::oneshot, future, AsyncRead, AsyncReadExt};
 use gtk::{gio, gio::prelude::*, glib, subclass::prelude::*};
 use nvim::{
     async_trait,
-    rpc::{caller::Response, Caller, HandleError, RpcWriter, WriteError},
+    rpc::{
+        caller::{Response, Sender},
+        message::{Request, Response as ResponseMessage},
+        record::{Direction, Recorder},
+        Caller, HandleError, Handler, RpcWriter, WriteError,
+    },
     serde,
 };
 
-use crate::nvim::imp::Connection;
+use crate::{
+    nvim::imp::{Connection, Stderr},
+    spawn_local,
+};
+
+/// Max amount of stderr output to keep from `Neovim::open_cmd`.
+const STDERR_MAX: usize = 16 * 1024;
+/// How long to wait for the command's stderr to close.
+const STDERR_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+/// How often to flush the session log, when recording.
+const RECORD_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Writer that collects everything to memory.
+fn sink_writer() -> gio::OutputStreamAsyncWrite<gio::PollableOutputStream> {
+    gio::MemoryOutputStream::new_resizable()
+        .dynamic_cast::<gio::PollableOutputStream>()
+        .expect("cast to PollableOutputStream")
+        .into_async_write()
+        .expect("convert to async write")
+}
+
+/// Reads `stderr` until it's closed, keeping the last `STDERR_MAX` bytes of
+/// it in `output`. Signals `closed` once done.
+async fn read_stderr<R: AsyncRead + Unpin>(
+    mut stderr: R,
+    output: Rc<RefCell<Vec<u8>>>,
+    closed: oneshot::Sender<()>,
+) {
+    let mut buf = [0; 4096];
+
+    loop {
+        match stderr.read(&mut buf).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                let mut output = output.borrow_mut();
+                output.extend_from_slice(&buf[..n]);
+                if output.len() > STDERR_MAX {
+                    let excess = output.len() - STDERR_MAX;
+                    output.drain(..excess);
+                }
+            }
+        }
+    }
+
+    let _ = closed.send(());
+}
 
 mod imp;
 
@@ -22,33 +72,34 @@ impl Neovim {
         glib::Object::new()
     }
 
-    /// Connect to a nvim process at `addr`.
-    pub fn connect(&self, addr: &str) -> gio::InputStreamAsyncRead<gio::PollableInputStream> {
+    /// Connect to a nvim process at `addr`. Until connected, anything
+    /// written goes to a sink.
+    pub async fn connect(
+        &self,
+        addr: &str,
+    ) -> Result<gio::InputStreamAsyncRead<gio::PollableInputStream>, glib::Error> {
         let imp = self.imp();
         assert!(
             imp.connection.borrow().is_none(),
             "shouldn't be started yet"
         );
 
+        imp.writer
+            .try_lock()
+            .expect("set rpc writer")
+            .get_or_insert_with(sink_writer);
+
         // If there is a ":" in the address, its a TCP address. Otherwise its
         // unix socket.
         // See `:h serverstart()`.
+        let client = gio::SocketClient::new();
         let conn = if addr.contains(":") {
-            SocketClientExt::connect_to_host(
-                &gio::SocketClient::new(),
-                addr,
-                0,
-                gio::Cancellable::NONE,
-            )
+            client.connect_to_host_future(addr, 0).await
         } else {
-            SocketClientExt::connect(
-                &gio::SocketClient::new(),
-                &gio::UnixSocketAddress::new(std::path::Path::new(addr)),
-                gio::Cancellable::NONE,
-            )
-        }
-        .map_err::<String, _>(|err| format!("failed to open {}: {}", addr, err))
-        .expect("failed to connect");
+            client
+                .connect_future(&gio::UnixSocketAddress::new(std::path::Path::new(addr)))
+                .await
+        }?;
 
         let writer = conn
             .output_stream()
@@ -72,8 +123,23 @@ impl Neovim {
         imp.connection
             .borrow_mut()
             .replace(Connection::Socket(conn));
+        imp.callbacks.borrow_mut().reconnect();
 
-        reader
+        Ok(reader)
+    }
+
+    /// Drops the connection to neovim, so that we can connect again.
+    ///
+    /// Pending calls, and calls made until we're connected again, fail with
+    /// `CallError::Disconnected`.
+    pub fn disconnect(&self) {
+        let imp = self.imp();
+        imp.connection.borrow_mut().take();
+        imp.callbacks.borrow_mut().disconnect();
+        imp.writer
+            .try_lock()
+            .expect("set rpc writer")
+            .replace(sink_writer());
     }
 
     /// Open the neovim subprocess.
@@ -88,20 +154,63 @@ impl Neovim {
         args: &[&OsStr],
         inherit_fds: bool,
     ) -> gio::InputStreamAsyncRead<gio::PollableInputStream> {
+        let mut flags = gio::SubprocessFlags::empty();
+        if inherit_fds {
+            flags.insert(gio::SubprocessFlags::INHERIT_FDS);
+        }
+
+        self.spawn(args, flags).1
+    }
+
+    /// Open a shell command that speaks msgpack-rpc over its stdin and stdout
+    /// (e.g. `ssh host nvim --embed`). The command's stderr is captured, see
+    /// `take_stderr`.
+    pub fn open_cmd(&self, cmd: &str) -> gio::InputStreamAsyncRead<gio::PollableInputStream> {
+        let mut args: Vec<&OsStr> = vec![];
+
+        #[cfg(feature = "flatpak")]
+        args.extend_from_slice(&["flatpak-spawn".as_ref(), "--host".as_ref()]);
+
+        args.extend_from_slice(&["sh".as_ref(), "-c".as_ref(), cmd.as_ref()]);
+
+        let (p, reader) = self.spawn(&args, gio::SubprocessFlags::STDERR_PIPE);
+
+        let stderr = p
+            .stderr_pipe()
+            .expect("get stderr pipe")
+            .dynamic_cast::<gio::PollableInputStream>()
+            .expect("cast to PollableInputStream")
+            .into_async_read()
+            .expect("covert to async read");
+
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let (tx, rx) = oneshot::channel();
+        self.imp().stderr.replace(Some(Stderr {
+            output: output.clone(),
+            closed: rx,
+        }));
+        spawn_local!(read_stderr(stderr, output, tx));
+
+        reader
+    }
+
+    fn spawn(
+        &self,
+        args: &[&OsStr],
+        mut flags: gio::SubprocessFlags,
+    ) -> (
+        gio::Subprocess,
+        gio::InputStreamAsyncRead<gio::PollableInputStream>,
+    ) {
         let imp = self.imp();
         assert!(
             imp.connection.borrow().is_none(),
             "shouldn't be started yet"
         );
 
-        let mut flags = gio::SubprocessFlags::empty();
         flags.insert(gio::SubprocessFlags::STDIN_PIPE);
         flags.insert(gio::SubprocessFlags::STDOUT_PIPE);
 
-        if inherit_fds {
-            flags.insert(gio::SubprocessFlags::INHERIT_FDS);
-        }
-
         let p = gio::Subprocess::newv(args, flags).expect("failed to open nvim subprocess");
 
         let writer = p
@@ -127,42 +236,129 @@ impl Neovim {
 
         imp.connection
             .borrow_mut()
-            .replace(Connection::Subprocess(p));
+            .replace(Connection::Subprocess(p.clone()));
 
-        reader
+        (p, reader)
     }
 
-    pub fn handle_response(&self, response: Response) -> Result<(), HandleError> {
-        let mut callbacks = self.imp().callbacks.borrow_mut();
-        let caller = callbacks
-            .iter()
-            .position(|(msgid, _)| *msgid == response.msgid)
-            .map(|index| callbacks.swap_remove(index));
-
-        match caller {
-            Some((_, recv)) => recv.send(response).map_err(HandleError::CallerDropped),
-            None => Err(HandleError::CallerMissing(response)),
+    /// Returns the captured stderr of the command opened with `open_cmd`.
+    /// Waits for the command to close it, but only for a moment, after which
+    /// the output read so far is returned.
+    pub async fn take_stderr(&self) -> Option<String> {
+        let Stderr { output, closed } = self.imp().stderr.take()?;
+
+        let _ = future::select(closed, glib::timeout_future(STDERR_TIMEOUT)).await;
+
+        let output = output.borrow();
+        Some(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// Sets up a connection that goes nowhere, for replaying a recorded
+    /// session. Anything written is collected to memory, and calls never get
+    /// a response.
+    pub fn open_replay(&self) {
+        let imp = self.imp();
+        assert!(
+            imp.connection.borrow().is_none(),
+            "shouldn't be started yet"
+        );
+
+        imp.writer
+            .try_lock()
+            .expect("set rpc writer")
+            .replace(sink_writer());
+    }
+
+    /// Starts recording the session to `path`. Incoming messages need to be
+    /// passed to `record`, outgoing messages are recorded automatically.
+    pub fn start_recording(&self, path: &str) -> std::io::Result<()> {
+        let file = File::create(path)?;
+        self.imp()
+            .recorder
+            .replace(Some(Recorder::new(BufWriter::new(file))));
+
+        // Flush the log every now and then, so that it's usable even if we
+        // don't get to drop (and thus flush) the recorder.
+        glib::timeout_add_local(
+            RECORD_FLUSH_INTERVAL,
+            glib::clone!(
+                #[weak(rename_to = obj)]
+                self,
+                #[upgrade_or_else]
+                || glib::ControlFlow::Break,
+                move || obj.flush_recording()
+            ),
+        );
+
+        Ok(())
+    }
+
+    fn flush_recording(&self) -> glib::ControlFlow {
+        let mut recorder = self.imp().recorder.borrow_mut();
+        let Some(ref mut rec) = *recorder else {
+            return glib::ControlFlow::Break;
+        };
+
+        if let Err(err) = rec.flush() {
+            crate::warn!("failed to flush recording, stopping recording: {}", err);
+            recorder.take();
+            return glib::ControlFlow::Break;
+        }
+
+        glib::ControlFlow::Continue
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.imp().recorder.borrow().is_some()
+    }
+
+    /// Records a message to the session log, if we're recording. On failure,
+    /// the recording is stopped.
+    pub fn record(&self, direction: Direction, data: &[u8]) {
+        let mut recorder = self.imp().recorder.borrow_mut();
+        let Some(ref mut rec) = *recorder else {
+            return;
+        };
+
+        if let Err(err) = rec.record(direction, data) {
+            crate::warn!("failed to record message, stopping recording: {}", err);
+            recorder.take();
         }
     }
 
+    async fn write_rpc<T: serde::Serialize>(&self, msg: &T) -> Result<(), WriteError> {
+        let data = <Vec<u8> as RpcWriter>::encode(msg)?;
+        self.record(Direction::Outgoing, &data);
+
+        let mut writer = self.imp().writer.lock().await;
+        RpcWriter::write(writer.as_mut().expect("nvim writer not set"), &data).await
+    }
+
+    pub fn handle_response(&self, response: Response) -> Result<(), HandleError> {
+        self.imp().callbacks.borrow_mut().handle_response(response)
+    }
+
     pub async fn write_rpc_response<R: serde::Serialize, E: serde::Serialize>(
         self,
         msgid: u32,
         error: Option<&E>,
         result: Option<&R>,
     ) -> Result<(), WriteError> {
-        self.imp()
-            .writer
-            .lock()
-            .await
-            .as_mut()
-            .expect("nvim writer not set")
-            .write_rpc_response(msgid, error, result)
+        self.write_rpc(&ResponseMessage::new(msgid, error, result))
             .await
     }
 
-    pub async fn write_empty_rpc_response(self, msgid: u32) -> Result<(), WriteError> {
-        self.write_rpc_response(msgid, None::<&()>, None::<&()>)
+    /// Handles `req` with `handler` and writes the response.
+    ///
+    /// Unlike `Handler::respond`, the writer is only locked once the request
+    /// is handled, so the handler can make calls to neovim.
+    pub async fn handle_request<H: Handler>(
+        self,
+        handler: &H,
+        req: Request<'_, rmpv::Value>,
+    ) -> Result<(), WriteError> {
+        let res = handler.handle_request(&req.method, req.params).await;
+        self.write_rpc_response(req.msgid, res.as_ref().err(), res.as_ref().ok())
             .await
     }
 }
@@ -175,13 +371,7 @@ impl Caller for &Neovim {
         method: S,
         args: V,
     ) -> Result<(), WriteError> {
-        self.imp()
-            .writer
-            .lock()
-            .await
-            .as_mut()
-            .expect("nvim writer not set")
-            .write_rpc_request(msgid, method.as_ref(), &args)
+        self.write_rpc(&Request::new(msgid, method.as_ref(), &args))
             .await
     }
 
@@ -194,8 +384,12 @@ impl Caller for &Neovim {
         msgid
     }
 
-    fn store_handler(&mut self, msgid: u32, sender: oneshot::Sender<Response>) {
-        self.imp().callbacks.borrow_mut().push((msgid, sender));
+    fn store_handler(&mut self, msgid: u32, sender: Sender) {
+        self.imp().callbacks.borrow_mut().insert(msgid, sender);
+    }
+
+    fn remove_handler(&mut self, msgid: u32) -> Option<Sender> {
+        self.imp().callbacks.borrow_mut().remove(msgid)
     }
 }
 